@@ -0,0 +1,115 @@
+//! In-memory reading of source archives (tar, tar.gz/tgz, zip).
+//!
+//! Entries are read into memory one at a time and handed to the caller, which runs
+//! them through the normal parser dispatch, so nothing is unpacked to disk.
+
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
+
+use flate2::read::GzDecoder;
+
+/// A regular file read from an archive.
+pub struct ArchiveEntry {
+    /// Path inside the archive, with `/` separators.
+    pub path: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+/// Whether `path` looks like an archive by its file name.
+pub fn is_archive_path(path: &str) -> bool {
+    let lc = path.to_ascii_lowercase();
+    [".tar", ".tar.gz", ".tgz", ".zip"]
+        .iter()
+        .any(|suffix| lc.ends_with(suffix))
+}
+
+/// Read the regular file entries of the archive at `path`, passing each one to `on_entry`
+/// as soon as it is read. Returns the number of entries skipped for exceeding `max_bytes`.
+///
+/// The format is detected from the leading magic bytes, not the file name,
+/// so `--extract` also works for archives with unusual extensions.
+pub fn read_archive(
+    path: &str,
+    max_bytes: u64,
+    on_entry: impl FnMut(ArchiveEntry),
+) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+
+    let mut magic = [0u8; 4];
+    let n = file.read(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let format = if n >= 4 && magic == *b"PK\x03\x04" {
+        ArchiveFormat::Zip
+    } else if n >= 2 && magic[..2] == [0x1f, 0x8b] {
+        ArchiveFormat::TarGz
+    } else {
+        ArchiveFormat::Tar
+    };
+
+    match format {
+        ArchiveFormat::Zip => read_zip(file, max_bytes, on_entry),
+        ArchiveFormat::TarGz => read_tar(GzDecoder::new(BufReader::new(file)), max_bytes, on_entry),
+        ArchiveFormat::Tar => read_tar(BufReader::new(file), max_bytes, on_entry),
+    }
+}
+
+fn read_tar<R: Read>(
+    reader: R,
+    max_bytes: u64,
+    mut on_entry: impl FnMut(ArchiveEntry),
+) -> io::Result<u64> {
+    let mut archive = tar::Archive::new(reader);
+    let mut skipped = 0;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        if entry.size() > max_bytes {
+            skipped += 1;
+            continue;
+        }
+
+        let path = entry.path()?.to_string_lossy().replace('\\', "/");
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        on_entry(ArchiveEntry { path, data });
+    }
+
+    Ok(skipped)
+}
+
+fn read_zip(file: File, max_bytes: u64, mut on_entry: impl FnMut(ArchiveEntry)) -> io::Result<u64> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    let mut skipped = 0;
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        if !entry.is_file() {
+            continue;
+        }
+        if entry.size() > max_bytes {
+            skipped += 1;
+            continue;
+        }
+
+        let path = entry.name().replace('\\', "/");
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        on_entry(ArchiveEntry { path, data });
+    }
+
+    Ok(skipped)
+}
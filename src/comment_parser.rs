@@ -6,15 +6,30 @@
 //! - Provide a best-effort treatment of string literals to avoid counting comment markers inside strings.
 //!   This is intentionally lightweight; it won't be a full lexer.
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::model::{ParserKind, SqlDialect};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseState {
     pub in_block_comment: bool,
+    /// Closing sequence of a raw string continuing across lines: `)delim"` in C++,
+    /// `` ` `` in Go, `"""` in C#, or `"` for a C# verbatim string (where `""` is escaped).
+    pub raw_string_end: Option<String>,
+    /// The previous line was a `//` comment ending in `\`, so this line continues it.
+    pub continued_comment: bool,
+}
+
+impl Default for ParseState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ParseState {
     pub fn new() -> Self {
         Self {
             in_block_comment: false,
+            raw_string_end: None,
+            continued_comment: false,
         }
     }
 }
@@ -35,62 +50,151 @@ pub fn classify_line_c_like(line: &str, state: &mut ParseState) -> (bool, bool)
     )
 }
 
-/// Python-like:
+/// Go: C-like, plus `` `...` `` raw strings that may span lines and contain comment markers.
+pub fn classify_line_go_like(line: &str, state: &mut ParseState) -> (bool, bool) {
+    classify_line_generic(
+        line,
+        state,
+        LineComment::DoubleSlash,
+        Some(BlockComment::SlashStar),
+        StringRules::Go,
+    )
+}
+
+/// C#: C-like, plus strings that may span lines and contain comment markers:
+/// - verbatim strings `@"..."` (also `$@"..."`), where `""` is an escaped quote
+/// - raw string literals delimited by three or more quotes (`"""..."""`, `$"""..."""`)
+pub fn classify_line_csharp_like(line: &str, state: &mut ParseState) -> (bool, bool) {
+    classify_line_generic(
+        line,
+        state,
+        LineComment::DoubleSlash,
+        Some(BlockComment::SlashStar),
+        StringRules::CSharp,
+    )
+}
+
+/// Python:
 /// - line comment: `#`
-/// - optional block comment: triple quotes (""" or ''')
+/// - docstrings: a string literal that forms a whole statement (e.g. `"""doc"""` on its own)
+///   is counted as comment; any other string, including triple-quoted ones used as values
+///   (`x = """SQL"""`), is code
+/// - string prefixes (`r`, `b`, `u`, `f`, `t` and combinations), backslash escapes and
+///   f-string replacement fields (`f"{d['#']}"`) are understood
 ///
-/// Note: triple-quoted strings are not always comments in Python, but for cloc-style counting
-/// treating them as comments is common.
+/// Statement position is tracked across lines through open brackets and `\` continuations,
+/// so a triple-quoted argument inside a multi-line call is not mistaken for a docstring.
 pub fn classify_line_python_like(line: &str, state: &mut PythonState) -> (bool, bool) {
     let trimmed = line.trim();
     if trimmed.is_empty() {
         return (false, false);
     }
 
-    // If we are inside triple quote block, find a closing delimiter (best-effort: last occurrence).
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    let mut at_stmt_start = state.bracket_depth == 0 && !state.continuation;
+    state.continuation = false;
+
+    // Continue a triple-quoted string opened on a previous line.
     if let Some(delim) = state.in_triple {
-        if let Some(end_idx) = trimmed.rfind(delim.as_str()) {
-            state.in_triple = None;
-            let tail = &trimmed[end_idx + delim.len()..];
-            let (c2, com2, _hash2, _ended2) = scan_for_hash_comment_outside_strings(tail);
-            return (c2, true || com2);
+        let s = PyString {
+            quote: delim.quote(),
+            triple: true,
+            fmt: state.in_fstring,
+        };
+        let Some(end) = scan_py_string_body(bytes, 0, s) else {
+            return if state.in_docstring {
+                (false, true)
+            } else {
+                (true, false)
+            };
+        };
+        state.in_triple = None;
+        if state.in_docstring {
+            saw_comment = true;
+        } else {
+            saw_code = true;
         }
-        return (false, true);
+        i = end;
+        at_stmt_start = false;
     }
 
-    // Outside triple quote block: scan for # (ignoring strings) and triple delimiters.
-    let mut saw_code = false;
-    let mut saw_comment = false;
+    while i < bytes.len() {
+        let b = bytes[i];
 
-    // First check for triple quote start outside strings.
-    if let Some((idx, delim)) = find_triple_start_outside_strings(line) {
-        // Before triple start, classify code/# comment.
-        let before = &line[..idx];
-        let (c, com, _hash, _ended) = scan_for_hash_comment_outside_strings(before);
-        saw_code |= c;
-        saw_comment |= com;
-
-        // From triple start onwards, it becomes a comment block.
-        // If it also ends on this line, we can still have code after.
-        let after = &line[idx + delim.len()..];
-        if let Some(end_idx) = find_substring_outside_strings(after, delim.as_str()) {
-            saw_comment |= true;
-            let tail = &after[end_idx + delim.len()..];
-            let (c2, com2, _hash2, _ended2) = scan_for_hash_comment_outside_strings(tail);
-            saw_code |= c2;
-            saw_comment |= com2;
-        } else {
-            state.in_triple = Some(delim);
-            saw_comment |= true;
+        if b.is_ascii_whitespace() {
+            i += 1;
+            continue;
         }
 
-        return (saw_code, saw_comment);
-    }
+        if b == b'#' {
+            saw_comment = true;
+            break;
+        }
+
+        if b == b'\\' && line[i + 1..].trim().is_empty() {
+            state.continuation = true;
+            saw_code = true;
+            break;
+        }
 
-    // No triple quotes: just handle # comments.
-    let (c, com, _hash, _ended) = scan_for_hash_comment_outside_strings(line);
-    saw_code |= c;
-    saw_comment |= com;
+        let at_token_start = i == 0 || !is_ident_byte(bytes[i - 1]);
+        if at_token_start && let Some((s, open_len)) = py_string_start(bytes, i) {
+            // Only plain/raw/unicode strings can be docstrings; bytes and f-strings are values.
+            let prefix = &bytes[i..i + open_len - if s.triple { 3 } else { 1 }];
+            let docstring = at_stmt_start
+                && !prefix
+                    .iter()
+                    .any(|c| matches!(c.to_ascii_lowercase(), b'b' | b'f' | b't'));
+            at_stmt_start = false;
+
+            match scan_py_string_body(bytes, i + open_len, s) {
+                Some(end) => {
+                    if docstring && py_rest_is_trivial(&bytes[end..]) {
+                        saw_comment = true;
+                    } else {
+                        saw_code = true;
+                    }
+                    i = end;
+                    continue;
+                }
+                None if s.triple => {
+                    state.in_triple = Some(if s.quote == b'"' {
+                        TripleDelim::Double
+                    } else {
+                        TripleDelim::Single
+                    });
+                    state.in_docstring = docstring;
+                    state.in_fstring = s.fmt;
+                    if docstring {
+                        saw_comment = true;
+                    } else {
+                        saw_code = true;
+                    }
+                    return (saw_code, saw_comment);
+                }
+                None => {
+                    // Unterminated single-quoted string: only valid with a trailing `\`.
+                    state.continuation = trimmed.ends_with('\\');
+                    saw_code = true;
+                    break;
+                }
+            }
+        }
+
+        match b {
+            b'(' | b'[' | b'{' => state.bracket_depth += 1,
+            b')' | b']' | b'}' => state.bracket_depth = state.bracket_depth.saturating_sub(1),
+            _ => {}
+        }
+        saw_code = true;
+        // `a = 1; "not a docstring"` still starts a new statement after `;`.
+        at_stmt_start = b == b';' && state.bracket_depth == 0;
+        i += 1;
+    }
 
     (saw_code, saw_comment)
 }
@@ -102,87 +206,270 @@ pub enum TripleDelim {
 }
 
 impl TripleDelim {
-    fn as_str(self) -> &'static str {
+    fn quote(self) -> u8 {
         match self {
-            TripleDelim::Double => "\"\"\"",
-            TripleDelim::Single => "'''",
+            TripleDelim::Double => b'"',
+            TripleDelim::Single => b'\'',
         }
     }
-
-    fn len(self) -> usize {
-        3
-    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PythonState {
+    /// Triple-quoted string still open at the end of the previous line.
     pub in_triple: Option<TripleDelim>,
+    /// Whether the open triple-quoted string is a docstring (comment) rather than a value (code).
+    pub in_docstring: bool,
+    /// Whether the open triple-quoted string is an f-string (has `{}` replacement fields).
+    pub in_fstring: bool,
+    /// Unclosed `(`, `[`, `{` from previous lines (implicit line joining).
+    pub bracket_depth: u32,
+    /// Previous line ended with a `\` continuation.
+    pub continuation: bool,
+}
+
+impl Default for PythonState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PythonState {
     pub fn new() -> Self {
-        Self { in_triple: None }
+        Self {
+            in_triple: None,
+            in_docstring: false,
+            in_fstring: false,
+            bracket_depth: 0,
+            continuation: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PyString {
+    quote: u8,
+    triple: bool,
+    /// f-string (or t-string): `{...}` fields may contain nested strings.
+    fmt: bool,
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// If a string literal (with optional prefix) starts at `i`, returns it and the length of
+/// prefix plus opening quote(s).
+fn py_string_start(bytes: &[u8], i: usize) -> Option<(PyString, usize)> {
+    let mut j = i;
+    let mut fmt = false;
+    while j < bytes.len() && j - i < 2 {
+        match bytes[j].to_ascii_lowercase() {
+            b'r' | b'b' | b'u' => {}
+            b'f' | b't' => fmt = true,
+            _ => break,
+        }
+        j += 1;
+    }
+
+    let quote = *bytes.get(j)?;
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+    let triple = match_at(bytes, j, &[quote; 3]).is_some();
+    let open_len = j - i + if triple { 3 } else { 1 };
+    Some((PyString { quote, triple, fmt }, open_len))
+}
+
+/// Scan a string body from `i` (just past the opening quotes).
+/// Returns the index after the closing quote(s), or `None` if the line ends first.
+///
+/// Raw strings need no special case here: a backslash still keeps the next quote from
+/// closing the string, it just stays in the value.
+fn scan_py_string_body(bytes: &[u8], mut i: usize, s: PyString) -> Option<usize> {
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'\\' {
+            i += 2;
+            continue;
+        }
+        if s.fmt && b == b'{' {
+            if bytes.get(i + 1) == Some(&b'{') {
+                i += 2;
+                continue;
+            }
+            i = skip_py_fstring_field(bytes, i + 1)?;
+            continue;
+        }
+        if b == s.quote {
+            if !s.triple {
+                return Some(i + 1);
+            }
+            if match_at(bytes, i, &[s.quote; 3]).is_some() {
+                return Some(i + 3);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Skip an f-string replacement field starting after its `{`; returns the index after `}`.
+/// Nested strings may reuse the outer quote character (Python 3.12+).
+fn skip_py_fstring_field(bytes: &[u8], mut i: usize) -> Option<usize> {
+    let mut depth = 0u32;
+    while i < bytes.len() {
+        let at_token_start = !is_ident_byte(bytes[i - 1]);
+        if at_token_start && let Some((s, open_len)) = py_string_start(bytes, i) {
+            i = scan_py_string_body(bytes, i + open_len, s)?;
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b'}' if depth == 0 => return Some(i + 1),
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        i += 1;
     }
+    None
 }
 
-/// Lua-like:
+/// Whether what follows a string literal leaves it as a standalone statement.
+fn py_rest_is_trivial(rest: &[u8]) -> bool {
+    let rest = String::from_utf8_lossy(rest);
+    let rest = rest.trim_start();
+    rest.is_empty() || rest.starts_with('#') || rest.starts_with(';')
+}
+
+/// Lua:
 /// - line comment: `--`
-/// - block comment: `--[[ ]]` (basic form)
-/// - string literals: single and double quotes
+/// - long comment: `--[[ ]]` and leveled forms `--[==[ ]==]`, closed only by the bracket of
+///   the same level
+/// - long strings: `[[ ]]` / `[==[ ]==]` are code, even when they contain `--`
+/// - short strings: single and double quotes with backslash escapes
 pub fn classify_line_lua_like(line: &str, state: &mut LuaState) -> (bool, bool) {
     let trimmed = line.trim();
     if trimmed.is_empty() {
         return (false, false);
     }
 
-    if state.in_long_comment {
-        if trimmed.contains("]]" ) {
-            // best-effort end detection; ignore strings here
-            if let Some(pos) = trimmed.find("]]" ) {
-                let after = &trimmed[pos + 2..];
-                state.in_long_comment = false;
-                if after.trim().is_empty() {
-                    return (false, true);
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if state.in_long_comment || state.in_long_string {
+            if state.in_long_comment {
+                saw_comment = true;
+            } else {
+                saw_code = true;
+            }
+            match find_lua_long_close(bytes, i, state.level) {
+                Some(end) => {
+                    state.in_long_comment = false;
+                    state.in_long_string = false;
+                    i = end;
+                    continue;
                 }
-                // continue classifying remainder after end of long comment
-                let (c2, com2) = classify_line_lua_like(after, state);
-                return (c2, true || com2);
+                None => return (saw_code, saw_comment),
             }
         }
-        return (false, true);
-    }
-
-    // Detect long comment start outside strings: --[[
-    if let Some(idx) = find_substring_outside_strings(line, "--[[") {
-        let before = &line[..idx];
-        let (c_before, com_before) = classify_line_lua_line_comment(before);
-        let after = &line[idx + 4..];
 
-        // If it also ends on this line
-        if let Some(end_idx) = find_substring_outside_strings(after, "]]" ) {
-            let tail = &after[end_idx + 2..];
-            let (c_tail, com_tail) = classify_line_lua_line_comment(tail);
-            return (c_before || c_tail, true || com_before || com_tail);
+        let b = bytes[i];
+        if match_at(bytes, i, b"--").is_some() {
+            saw_comment = true;
+            if let Some((level, len)) = lua_long_open(bytes, i + 2) {
+                state.in_long_comment = true;
+                state.level = level;
+                i += 2 + len;
+                continue;
+            }
+            break;
         }
-
-        state.in_long_comment = true;
-        return (c_before, true || com_before);
+        if b == b'['
+            && let Some((level, len)) = lua_long_open(bytes, i)
+        {
+            state.in_long_string = true;
+            state.level = level;
+            saw_code = true;
+            i += len;
+            continue;
+        }
+        if b == b'\'' || b == b'"' {
+            saw_code = true;
+            i = scan_quoted(bytes, i + 1, b).unwrap_or(bytes.len());
+            continue;
+        }
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
     }
 
-    classify_line_lua_line_comment(line)
+    (saw_code, saw_comment)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LuaState {
+    /// Inside a `--[[ ]]` long comment.
     pub in_long_comment: bool,
+    /// Inside a `[[ ]]` long string.
+    pub in_long_string: bool,
+    /// Number of `=` signs in the open long bracket.
+    pub level: usize,
+}
+
+impl Default for LuaState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LuaState {
     pub fn new() -> Self {
         Self {
             in_long_comment: false,
+            in_long_string: false,
+            level: 0,
+        }
+    }
+}
+
+/// Opening long bracket (`[[`, `[=[`, ...) at `i`: returns its level and length.
+fn lua_long_open(bytes: &[u8], i: usize) -> Option<(usize, usize)> {
+    if bytes.get(i) != Some(&b'[') {
+        return None;
+    }
+    let level = bytes[i + 1..].iter().take_while(|&&b| b == b'=').count();
+    (bytes.get(i + 1 + level) == Some(&b'[')).then_some((level, level + 2))
+}
+
+/// Find the closing long bracket of `level` from `i`; returns the index after it.
+fn find_lua_long_close(bytes: &[u8], mut i: usize, level: usize) -> Option<usize> {
+    while i < bytes.len() {
+        if bytes[i] == b']' {
+            let eq = bytes[i + 1..].iter().take_while(|&&b| b == b'=').count();
+            if eq == level && bytes.get(i + 1 + level) == Some(&b']') {
+                return Some(i + level + 2);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Scan a backslash-escaped string body from `i`; returns the index after the closing quote.
+fn scan_quoted(bytes: &[u8], mut i: usize, quote: u8) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
         }
     }
+    None
 }
 
 /// XML/HTML-like: <!-- --> block comments. Strings are ignored.
@@ -223,6 +510,10 @@ enum BlockComment {
 enum StringRules {
     None,
     CStyle,
+    /// C-style plus Go raw strings.
+    Go,
+    /// C-style plus C# verbatim and raw strings.
+    CSharp,
 }
 
 fn classify_line_generic(
@@ -250,17 +541,15 @@ fn classify_line_generic(
         // Handle block comment mode first
         if state.in_block_comment {
             // look for end delimiter
-            if let Some((end_len, matched)) = match block_comment {
-                Some(BlockComment::SlashStar) => match_at(bytes, i, b"*/").map(|_| (2, true)),
-                Some(BlockComment::Xml) => match_at(bytes, i, b"-->").map(|_| (3, true)),
+            if let Some(end_len) = match block_comment {
+                Some(BlockComment::SlashStar) => match_at(bytes, i, b"*/").map(|_| 2),
+                Some(BlockComment::Xml) => match_at(bytes, i, b"-->").map(|_| 3),
                 None => None,
             } {
-                if matched {
-                    state.in_block_comment = false;
-                    saw_comment = true;
-                    i += end_len;
-                    continue;
-                }
+                state.in_block_comment = false;
+                saw_comment = true;
+                i += end_len;
+                continue;
             }
             // still in comment
             saw_comment = true;
@@ -268,8 +557,26 @@ fn classify_line_generic(
             continue;
         }
 
+        // Multi-line raw strings
+        if let Some(end) = &state.raw_string_end {
+            saw_code = true;
+            let found = if string_rules == StringRules::CSharp {
+                find_csharp_string_end(bytes, i, end)
+            } else {
+                find_closing(bytes, i, end.as_bytes())
+            };
+            match found {
+                Some(after) => {
+                    state.raw_string_end = None;
+                    i = after;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
         // Handle strings (best-effort)
-        if string_rules == StringRules::CStyle {
+        if string_rules != StringRules::None {
             let b = bytes[i];
             if in_string_single {
                 if b == b'\\' {
@@ -294,6 +601,21 @@ fn classify_line_generic(
                 continue;
             }
 
+            if string_rules == StringRules::Go && b == b'`' {
+                state.raw_string_end = Some("`".to_string());
+                saw_code = true;
+                i += 1;
+                continue;
+            }
+            if string_rules == StringRules::CSharp
+                && let Some((open, end)) = csharp_string_start(bytes, i)
+            {
+                state.raw_string_end = Some(end);
+                saw_code = true;
+                i = open;
+                continue;
+            }
+
             if b == b'\'' {
                 in_string_single = true;
                 saw_code = true;
@@ -324,12 +646,10 @@ fn classify_line_generic(
         }
 
         // Line comment start
-        if line_comment == LineComment::DoubleSlash {
-            if match_at(bytes, i, b"//").is_some() {
-                // anything after is comment
-                saw_comment = true;
-                break;
-            }
+        if line_comment == LineComment::DoubleSlash && match_at(bytes, i, b"//").is_some() {
+            // anything after is comment
+            saw_comment = true;
+            break;
         }
 
         // Any non-whitespace outside comments is considered code.
@@ -342,6 +662,59 @@ fn classify_line_generic(
     (saw_code, saw_comment)
 }
 
+/// Find a closing sequence (`*/`, the end of a raw string, ...) from `i`; returns the
+/// index after it.
+fn find_closing(bytes: &[u8], i: usize, end: &[u8]) -> Option<usize> {
+    (i..bytes.len())
+        .find(|&j| match_at(bytes, j, end).is_some())
+        .map(|j| j + end.len())
+}
+
+/// Like `find_closing`, for the closing sequence from `csharp_string_start`: a
+/// verbatim string (closed by a single `"`) treats `""` as an escaped quote.
+fn find_csharp_string_end(bytes: &[u8], mut i: usize, end: &str) -> Option<usize> {
+    if end != "\"" {
+        return find_closing(bytes, i, end.as_bytes());
+    }
+    while i < bytes.len() {
+        if bytes[i] == b'"' {
+            if bytes.get(i + 1) != Some(&b'"') {
+                return Some(i + 1);
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+/// If a C# verbatim (`@"`, `$@"`, `@$"`) or raw (`"""`, `$"""`) string starts at `i`,
+/// returns the index after its opening quotes and its closing sequence.
+fn csharp_string_start(bytes: &[u8], i: usize) -> Option<(usize, String)> {
+    let mut j = i;
+    while bytes.get(j) == Some(&b'$') {
+        j += 1;
+    }
+    let verbatim = bytes.get(j) == Some(&b'@');
+    if verbatim {
+        j += 1;
+        while bytes.get(j) == Some(&b'$') {
+            j += 1;
+        }
+    }
+    let quotes = bytes[j.min(bytes.len())..]
+        .iter()
+        .take_while(|&&b| b == b'"')
+        .count();
+    if quotes >= 3 && !verbatim {
+        Some((j + quotes, "\"".repeat(quotes)))
+    } else if quotes >= 1 && verbatim {
+        Some((j + 1, "\"".to_string()))
+    } else {
+        None
+    }
+}
+
 fn match_at(hay: &[u8], idx: usize, needle: &[u8]) -> Option<()> {
     if idx + needle.len() > hay.len() {
         return None;
@@ -353,56 +726,197 @@ fn match_at(hay: &[u8], idx: usize, needle: &[u8]) -> Option<()> {
     }
 }
 
-fn scan_for_hash_comment_outside_strings(line: &str) -> (bool, bool, bool, bool) {
-    // returns (saw_code, saw_comment, saw_hash_comment, ended_early)
+/// Batch/CMD-like:
+/// - comment line: leading `REM` (case-insensitive) or leading `::`
+///
+/// This is a best-effort heuristic used for cloc-style counting.
+pub fn classify_line_batch_like(line: &str) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    // `::` comment (common batch trick). Only treat it as comment when it appears at start.
+    if trimmed.starts_with("::") {
+        return (false, true);
+    }
+
+    // `REM` comment. Only treat it as comment when it appears at start and is a whole word.
+    // Accept: REM, REM<space>, REM\t...
+    // Reject: REMARK, REM1, echo REM ... (since it's not at line start after trim).
+    let t = trimmed;
+    // `get` rather than slicing: byte 3 may fall inside a multi-byte character.
+    if let Some(prefix) = t.get(..3)
+        && prefix.eq_ignore_ascii_case("rem")
+    {
+        let rest = &t[3..];
+        if rest.is_empty() {
+            return (false, true);
+        }
+        if rest.chars().next().is_some_and(|c| c.is_ascii_whitespace()) {
+            return (false, true);
+        }
+    }
+
+    (true, false)
+}
+
+/// SQL, per `state.dialect`:
+/// - line comments: `--` (MySQL requires whitespace after it), plus `#` in MySQL
+/// - block comments: `/* */`, nesting in PostgreSQL and T-SQL
+/// - strings: `'...'` and `"..."` with doubled-quote escapes (`'it''s'`), which may span lines;
+///   backslash escapes only in MySQL and PostgreSQL `E'...'` strings
+/// - PostgreSQL `$$ ... $$` / `$tag$ ... $tag$` quotes count as code, except routine bodies
+///   (a quote right after `AS` / `DO`, or at the start of a line): those are PL/pgSQL or SQL
+///   and use these rules, so `-- note` inside one is a comment. A `LANGUAGE` clause naming
+///   another language (e.g. `plpython3u`) before the body makes it plain code; one after the
+///   body comes too late to matter
+/// - MySQL `` `...` `` and T-SQL `[...]` identifiers count as code; both may span lines
+pub fn classify_line_sql_like(line: &str, state: &mut SqlState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let dialect = state.dialect;
+    let nested = matches!(dialect, SqlDialect::Postgres | SqlDialect::TSql);
     let bytes = line.as_bytes();
-    let mut i = 0usize;
-    let mut in_single = false;
-    let mut in_double = false;
     let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
 
     while i < bytes.len() {
-        let b = bytes[i];
-        if in_single {
-            if b == b'\\' {
-                i += 2;
-                continue;
-            }
-            if b == b'\'' {
-                in_single = false;
-            }
+        // To the outer statement a routine body is one string: its tag ends it wherever it is.
+        if let Some(tag) = &state.body_tag
+            && match_at(bytes, i, tag.as_bytes()).is_some()
+        {
+            i += tag.len();
             saw_code = true;
-            i += 1;
+            state.body_tag = None;
+            state.comment_depth = 0;
+            state.in_quote = None;
+            state.dollar_tag = None;
             continue;
         }
-        if in_double {
-            if b == b'\\' {
+
+        if state.comment_depth > 0 {
+            saw_comment = true;
+            if match_at(bytes, i, b"*/").is_some() {
+                state.comment_depth -= 1;
                 i += 2;
-                continue;
+            } else if nested && match_at(bytes, i, b"/*").is_some() {
+                state.comment_depth += 1;
+                i += 2;
+            } else {
+                i += 1;
             }
-            if b == b'"' {
-                in_double = false;
-            }
-            saw_code = true;
-            i += 1;
             continue;
         }
 
-        if b == b'\'' {
-            in_single = true;
+        if let Some(tag) = &state.dollar_tag {
             saw_code = true;
-            i += 1;
-            continue;
+            match find_closing(bytes, i, tag.as_bytes()) {
+                Some(end) => {
+                    i = end;
+                    state.dollar_tag = None;
+                    continue;
+                }
+                None => break,
+            }
         }
-        if b == b'"' {
-            in_double = true;
+
+        if let Some((q, backslash)) = state.in_quote {
             saw_code = true;
-            i += 1;
+            match scan_sql_quote(bytes, i, q, backslash) {
+                Some(end) => {
+                    state.in_quote = None;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let b = bytes[i];
+        if match_at(bytes, i, b"/*").is_some() {
+            state.comment_depth = 1;
+            saw_comment = true;
+            i += 2;
             continue;
         }
+        let line_comment = match dialect {
+            SqlDialect::MySql => {
+                b == b'#'
+                    || (match_at(bytes, i, b"--").is_some()
+                        && bytes.get(i + 2).is_none_or(u8::is_ascii_whitespace))
+            }
+            _ => match_at(bytes, i, b"--").is_some(),
+        };
+        if line_comment {
+            saw_comment = true;
+            let body_end = state.body_tag.as_ref().and_then(|tag| {
+                (i..bytes.len()).find(|&j| match_at(bytes, j, tag.as_bytes()).is_some())
+            });
+            match body_end {
+                Some(end) => {
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
 
-        if b == b'#' {
-            return (saw_code, true, true, true);
+        match b {
+            b'\'' | b'"' => {
+                let escape_string = dialect == SqlDialect::Postgres
+                    && b == b'\''
+                    && i > 0
+                    && bytes[i - 1].eq_ignore_ascii_case(&b'e')
+                    && (i < 2 || !is_ident_byte(bytes[i - 2]));
+                state.in_quote = Some((b, dialect == SqlDialect::MySql || escape_string));
+                saw_code = true;
+                i += 1;
+                continue;
+            }
+            b'`' if dialect == SqlDialect::MySql => {
+                state.in_quote = Some((b, false));
+                saw_code = true;
+                i += 1;
+                continue;
+            }
+            b'[' if dialect == SqlDialect::TSql => {
+                // `]]` is an escaped `]`, like a doubled quote.
+                state.in_quote = Some((b']', false));
+                saw_code = true;
+                i += 1;
+                continue;
+            }
+            b'$' if dialect == SqlDialect::Postgres => {
+                if let Some(tag) = sql_dollar_tag(bytes, i) {
+                    i += tag.len();
+                    let before = line[..i - tag.len()].trim_end();
+                    let opens_body = state.body_tag.is_none()
+                        && !state.opaque_body
+                        && (before.is_empty()
+                            || ends_with_word(before, "as")
+                            || ends_with_word(before, "do"));
+                    if opens_body {
+                        state.body_tag = Some(tag);
+                    } else {
+                        state.dollar_tag = Some(tag);
+                    }
+                    saw_code = true;
+                    continue;
+                }
+            }
+            b';' if state.body_tag.is_none() => state.opaque_body = false,
+            b'l' | b'L' if dialect == SqlDialect::Postgres && state.body_tag.is_none() => {
+                if let Some(lang) = sql_language_clause(bytes, i) {
+                    state.opaque_body =
+                        !lang.eq_ignore_ascii_case("plpgsql") && !lang.eq_ignore_ascii_case("sql");
+                }
+            }
+            _ => {}
         }
 
         if !b.is_ascii_whitespace() {
@@ -411,315 +925,2981 @@ fn scan_for_hash_comment_outside_strings(line: &str) -> (bool, bool, bool, bool)
         i += 1;
     }
 
-    (saw_code, false, false, false)
+    (saw_code, saw_comment)
 }
 
-fn find_triple_start_outside_strings(line: &str) -> Option<(usize, TripleDelim)> {
-    let mut i = 0usize;
-    let bytes = line.as_bytes();
-    let mut in_single = false;
-    let mut in_double = false;
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SqlState {
+    pub dialect: SqlDialect,
+    /// Number of open `/* */` comments (only PostgreSQL and T-SQL nest them).
+    pub comment_depth: u32,
+    /// Open quote continuing across lines, and whether backslash escapes apply.
+    pub in_quote: Option<(u8, bool)>,
+    /// Open PostgreSQL dollar quote, e.g. `$$` or `$body$`.
+    pub dollar_tag: Option<String>,
+    /// Tag of an open PostgreSQL routine body (`AS $$`), whose lines use the SQL rules.
+    pub body_tag: Option<String>,
+    /// A `LANGUAGE` clause earlier in the statement names a language other than PL/pgSQL or
+    /// SQL, so a body opened now is plain code.
+    pub opaque_body: bool,
+}
+
+impl SqlState {
+    pub fn new(dialect: SqlDialect) -> Self {
+        Self {
+            dialect,
+            ..Self::default()
+        }
+    }
+}
 
+/// Scan a quoted SQL body from `i`; returns the index after the closing quote.
+/// A doubled quote is an escaped quote; backslash escapes only apply when `backslash` is set.
+fn scan_sql_quote(bytes: &[u8], mut i: usize, quote: u8, backslash: bool) -> Option<usize> {
     while i < bytes.len() {
         let b = bytes[i];
-        if in_single {
-            if b == b'\\' {
+        if backslash && b == b'\\' {
+            i += 2;
+            continue;
+        }
+        if b == quote {
+            if bytes.get(i + 1) == Some(&quote) {
                 i += 2;
                 continue;
             }
-            if b == b'\'' {
-                in_single = false;
+            return Some(i + 1);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The PostgreSQL dollar-quote delimiter starting at `i` (`$$` or `$tag$`), if any.
+/// `$1` parameters are not delimiters, since a tag cannot start with a digit.
+fn sql_dollar_tag(bytes: &[u8], i: usize) -> Option<String> {
+    if i > 0 && is_ident_byte(bytes[i - 1]) {
+        return None;
+    }
+    let start = i + 1;
+    let len = bytes[start..]
+        .iter()
+        .take_while(|&&b| is_ident_byte(b))
+        .count();
+    if len > 0 && bytes[start].is_ascii_digit() {
+        return None;
+    }
+    if bytes.get(start + len) != Some(&b'$') {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes[i..start + len + 1]).into_owned())
+}
+
+/// Whether `text` ends with `word` (ASCII case-insensitive) as a whole word.
+fn ends_with_word(text: &str, word: &str) -> bool {
+    let Some(start) = text.len().checked_sub(word.len()) else {
+        return false;
+    };
+    text.is_char_boundary(start)
+        && text[start..].eq_ignore_ascii_case(word)
+        && (start == 0 || !is_ident_byte(text.as_bytes()[start - 1]))
+}
+
+/// The language name of a `LANGUAGE name` clause starting at `i`, e.g. `plpgsql`.
+fn sql_language_clause(bytes: &[u8], i: usize) -> Option<&str> {
+    let keyword = b"language";
+    if (i > 0 && is_ident_byte(bytes[i - 1]))
+        || !bytes
+            .get(i..i + keyword.len())?
+            .eq_ignore_ascii_case(keyword)
+    {
+        return None;
+    }
+    let mut start = i + keyword.len();
+    if !bytes.get(start)?.is_ascii_whitespace() {
+        return None;
+    }
+    while bytes.get(start).is_some_and(u8::is_ascii_whitespace) {
+        start += 1;
+    }
+    // The name may be quoted: `LANGUAGE 'plpgsql'`.
+    if matches!(bytes.get(start), Some(b'\'' | b'"')) {
+        start += 1;
+    }
+    let len = bytes[start..]
+        .iter()
+        .take_while(|&&b| is_ident_byte(b))
+        .count();
+    let name = std::str::from_utf8(&bytes[start..start + len]).ok()?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// Shell family (sh, bash, zsh, ksh, fish):
+/// - line comment: `#`, but only at the start of a word, so `${var#prefix}`, `$#` and `a#b`
+///   are code
+/// - quotes: `'...'`, `"..."` and `$'...'`, which may span lines
+/// - heredocs: `<<EOF`, `<<-EOF`, `<<'EOF'`; body lines are code (data), except for the
+///   `: <<'EOF'` idiom, whose body is a block comment
+pub fn classify_line_shell_like(line: &str, state: &mut ShellState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    // Heredoc body: read verbatim until the terminator line.
+    if let Some(doc) = state.heredocs.first() {
+        let body = if doc.strip_tabs {
+            line.trim_start_matches('\t')
+        } else {
+            line
+        };
+        let is_comment = doc.comment;
+        if body.trim_end() == doc.delim {
+            state.heredocs.remove(0);
+        }
+        return if is_comment {
+            (false, true)
+        } else {
+            (true, false)
+        };
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+    let mut word_start = true;
+    let mut brace_depth = 0u32;
+    // Open parentheses inside `((...))` / `$((...))` arithmetic, where `<<` is a shift.
+    let mut arith_parens = 0u32;
+
+    while i < bytes.len() {
+        if let Some(q) = state.in_quote {
+            saw_code = true;
+            match scan_shell_quote(bytes, i, q) {
+                Some(end) => {
+                    state.in_quote = None;
+                    i = end;
+                    word_start = false;
+                    continue;
+                }
+                None => return (saw_code, saw_comment),
             }
+        }
+
+        let b = bytes[i];
+        if b.is_ascii_whitespace() {
+            word_start = true;
             i += 1;
             continue;
         }
-        if in_double {
-            if b == b'\\' {
+
+        if b == b'#' && word_start && brace_depth == 0 {
+            saw_comment = true;
+            break;
+        }
+
+        saw_code = true;
+        match b {
+            b'\'' => {
+                let ansi = i > 0 && bytes[i - 1] == b'$';
+                state.in_quote = Some(if ansi {
+                    ShellQuote::Ansi
+                } else {
+                    ShellQuote::Single
+                });
+                i += 1;
+                continue;
+            }
+            b'"' => {
+                state.in_quote = Some(ShellQuote::Double);
+                i += 1;
+                continue;
+            }
+            b'\\' => {
+                // `\#` and friends are literal characters.
+                word_start = false;
                 i += 2;
                 continue;
             }
-            if b == b'"' {
-                in_double = false;
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                brace_depth += 1;
+                word_start = false;
+                i += 2;
+                continue;
             }
-            i += 1;
+            b'}' if brace_depth > 0 => {
+                brace_depth -= 1;
+                i += 1;
+                continue;
+            }
+            b'$' if match_at(bytes, i + 1, b"((").is_some() => {
+                arith_parens += 2;
+                word_start = true;
+                i += 3;
+                continue;
+            }
+            b'(' if word_start && bytes.get(i + 1) == Some(&b'(') => {
+                arith_parens += 2;
+                i += 2;
+                continue;
+            }
+            b'(' if arith_parens > 0 => {
+                arith_parens += 1;
+                word_start = true;
+            }
+            b')' if arith_parens > 0 => {
+                arith_parens -= 1;
+                word_start = true;
+            }
+            b'<' if arith_parens == 0
+                && match_at(bytes, i, b"<<").is_some()
+                && match_at(bytes, i, b"<<<").is_none() =>
+            {
+                let command = line[..i].trim();
+                let (doc, end) = parse_heredoc_start(bytes, i + 2, command == ":");
+                if let Some(doc) = doc {
+                    state.heredocs.push(doc);
+                }
+                word_start = true;
+                i = end;
+                continue;
+            }
+            b';' | b'|' | b'&' | b'(' | b')' => word_start = true,
+            _ => word_start = false,
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellQuote {
+    /// `'...'`: no escapes.
+    Single,
+    /// `"..."`: backslash escapes.
+    Double,
+    /// `$'...'`: backslash escapes.
+    Ansi,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heredoc {
+    /// Terminator word, with quotes removed.
+    pub delim: String,
+    /// `<<-`: leading tabs are stripped from body and terminator lines.
+    pub strip_tabs: bool,
+    /// Body of a `: <<EOF` heredoc, a common block comment idiom.
+    pub comment: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellState {
+    /// Quote still open at the end of the previous line.
+    pub in_quote: Option<ShellQuote>,
+    /// Heredocs whose bodies are pending; the first one is being read.
+    pub heredocs: Vec<Heredoc>,
+}
+
+impl ShellState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Scan a quoted string body from `i`; returns the index after the closing quote.
+fn scan_shell_quote(bytes: &[u8], mut i: usize, q: ShellQuote) -> Option<usize> {
+    let close = match q {
+        ShellQuote::Single | ShellQuote::Ansi => b'\'',
+        ShellQuote::Double => b'"',
+    };
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'\\' && q != ShellQuote::Single {
+            i += 2;
             continue;
         }
+        if b == close {
+            return Some(i + 1);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parse the terminator after `<<` (at `i`); returns the heredoc and the index after the word.
+fn parse_heredoc_start(bytes: &[u8], mut i: usize, comment: bool) -> (Option<Heredoc>, usize) {
+    let strip_tabs = bytes.get(i) == Some(&b'-');
+    if strip_tabs {
+        i += 1;
+    }
+    while i < bytes.len() && (bytes[i] == b' ' || bytes[i] == b'\t') {
+        i += 1;
+    }
+    // A terminator is a word such as `EOF`, `'EOF'` or `\EOF`; `<< 2` or `<<=` is not a heredoc.
+    if !bytes
+        .get(i)
+        .is_some_and(|&b| b.is_ascii_alphabetic() || b"_'\"\\".contains(&b))
+    {
+        return (None, i.min(bytes.len()));
+    }
 
-        if match_at(bytes, i, b"\"\"\"").is_some() {
-            return Some((i, TripleDelim::Double));
+    let mut delim = Vec::new();
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'\'' | b'"' => {
+                // Quoted terminator: take everything up to the matching quote.
+                i += 1;
+                while i < bytes.len() && bytes[i] != b {
+                    delim.push(bytes[i]);
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'\\' => i += 1,
+            _ if b.is_ascii_whitespace() || b";|&<>()".contains(&b) => break,
+            _ => {
+                delim.push(b);
+                i += 1;
+            }
         }
-        if match_at(bytes, i, b"'''").is_some() {
-            return Some((i, TripleDelim::Single));
+    }
+
+    if delim.is_empty() {
+        return (None, i.min(bytes.len()));
+    }
+    let doc = Heredoc {
+        delim: String::from_utf8_lossy(&delim).into_owned(),
+        strip_tabs,
+        comment,
+    };
+    (Some(doc), i.min(bytes.len()))
+}
+
+/// YAML:
+/// - line comment: `#` at line start or after whitespace, outside quotes
+/// - quoted scalars: `'...'` (`''` escape) and `"..."` (backslash escapes), which may span lines
+/// - block scalars: after a `|` or `>` indicator, every more-indented line is content (code),
+///   whatever markers it contains
+/// - document markers `---` / `...` are code
+pub fn classify_line_yaml_like(line: &str, state: &mut YamlState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let indent = line.len() - line.trim_start().len();
+    if let Some(parent) = state.block_indent {
+        if indent > parent {
+            return (true, false);
         }
+        state.block_indent = None;
+    }
 
-        if b == b'\'' {
-            in_single = true;
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if let Some(q) = state.in_quote {
+            saw_code = true;
+            match scan_yaml_quote(bytes, i, q) {
+                Some(end) => {
+                    state.in_quote = None;
+                    i = end;
+                    continue;
+                }
+                None => return (saw_code, saw_comment),
+            }
+        }
+
+        let b = bytes[i];
+        if b == b'#' && (i == 0 || bytes[i - 1].is_ascii_whitespace()) {
+            saw_comment = true;
+            break;
+        }
+        // Quotes only open a scalar at the start of a token (`it's` is plain text).
+        if (b == b'\'' || b == b'"')
+            && (i == 0 || bytes[i - 1].is_ascii_whitespace() || b"[{,:-?".contains(&bytes[i - 1]))
+        {
+            state.in_quote = Some(b);
+            saw_code = true;
             i += 1;
             continue;
         }
-        if b == b'"' {
-            in_double = true;
-            i += 1;
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    if state.in_quote.is_none() && yaml_starts_block_scalar(&line[..i]) {
+        state.block_indent = Some(indent);
+    }
+
+    (saw_code, saw_comment)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YamlState {
+    /// Quote (`'` or `"`) of a flow scalar still open at the end of the previous line.
+    pub in_quote: Option<u8>,
+    /// Indentation of the line that introduced an open block scalar.
+    pub block_indent: Option<usize>,
+}
+
+impl Default for YamlState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl YamlState {
+    pub fn new() -> Self {
+        Self {
+            in_quote: None,
+            block_indent: None,
+        }
+    }
+}
+
+fn scan_yaml_quote(bytes: &[u8], mut i: usize, q: u8) -> Option<usize> {
+    while i < bytes.len() {
+        let b = bytes[i];
+        if q == b'"' && b == b'\\' {
+            i += 2;
             continue;
         }
+        if b == q {
+            // `''` inside a single-quoted scalar is an escaped quote.
+            if q == b'\'' && bytes.get(i + 1) == Some(&b'\'') {
+                i += 2;
+                continue;
+            }
+            return Some(i + 1);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether the code part of a line ends with a block scalar header (`|`, `>-`, `|2+`, ...).
+fn yaml_starts_block_scalar(code: &str) -> bool {
+    let code = code.trim_end();
+    let header_start = code
+        .rfind(|c: char| c.is_ascii_whitespace())
+        .map(|p| p + 1)
+        .unwrap_or(0);
+    let header = &code[header_start..];
+    let before = code[..header_start].trim_end();
+
+    let mut chars = header.chars();
+    let valid_header = matches!(chars.next(), Some('|' | '>'))
+        && chars.all(|c| c == '-' || c == '+' || c.is_ascii_digit());
+    // The indicator must be a value: after `key:`, a sequence dash, or alone on the line.
+    valid_header && (before.is_empty() || before.ends_with(':') || before.ends_with('-'))
+}
+
+/// TOML:
+/// - line comment: `#` outside strings
+/// - strings: basic `"..."`, literal `'...'`, and multi-line `"""..."""` / `'''...'''`;
+///   multi-line strings are code on every line they span
+/// - quoted keys and table headers (`"a#b" = 1`, `[x."y#z"]`) are code
+pub fn classify_line_toml_like(line: &str, state: &mut TomlState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if let Some(delim) = state.in_multiline {
+            saw_code = true;
+            match scan_toml_string(bytes, i, delim.quote(), true) {
+                Some(end) => {
+                    state.in_multiline = None;
+                    i = end;
+                    continue;
+                }
+                None => return (saw_code, saw_comment),
+            }
+        }
+
+        let b = bytes[i];
+        if b == b'#' {
+            saw_comment = true;
+            break;
+        }
+        if b == b'"' || b == b'\'' {
+            saw_code = true;
+            let triple = match_at(bytes, i, &[b; 3]).is_some();
+            let open_len = if triple { 3 } else { 1 };
+            match scan_toml_string(bytes, i + open_len, b, triple) {
+                Some(end) => i = end,
+                None if triple => {
+                    state.in_multiline = Some(if b == b'"' {
+                        TripleDelim::Double
+                    } else {
+                        TripleDelim::Single
+                    });
+                    return (saw_code, saw_comment);
+                }
+                // Unterminated single-line string: invalid TOML, stop at end of line.
+                None => break,
+            }
+            continue;
+        }
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TomlState {
+    /// Multi-line string still open at the end of the previous line.
+    pub in_multiline: Option<TripleDelim>,
+}
+
+impl Default for TomlState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TomlState {
+    pub fn new() -> Self {
+        Self { in_multiline: None }
+    }
+}
+
+/// Scan a TOML string body from `i`; returns the index after the closing delimiter.
+/// Only basic (`"`) strings have backslash escapes.
+fn scan_toml_string(bytes: &[u8], mut i: usize, quote: u8, triple: bool) -> Option<usize> {
+    while i < bytes.len() {
+        let b = bytes[i];
+        if quote == b'"' && b == b'\\' {
+            i += 2;
+            continue;
+        }
+        if b == quote {
+            if !triple {
+                return Some(i + 1);
+            }
+            if match_at(bytes, i, &[quote; 3]).is_some() {
+                // Up to two extra quotes right before the delimiter belong to the content.
+                let mut end = i + 3;
+                while end < bytes.len() && bytes[end] == quote && end - i < 5 {
+                    end += 1;
+                }
+                return Some(end);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// HTML-like host documents (HTML, Vue and Svelte single-file components):
+/// - markup: `<!-- -->` comments
+/// - `<script>` regions: JavaScript rules (template literals, regex literals); non-script `type`s
+///   are plain text
+/// - `<style>` regions: CSS rules (`/* */`)
+///
+/// After each line, `state.line_lang` names the embedded language when the line lies
+/// entirely inside a `<script>` / `<style>` region (e.g. `"js"`, `"ts"`, `"css"`, `"scss"`),
+/// so callers can credit it to that language instead of the host. Blank lines inside a
+/// region name it too.
+pub fn classify_line_html_like(line: &str, state: &mut HtmlState) -> (bool, bool) {
+    state.line_lang = None;
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        if state.region != HtmlRegion::Markup {
+            state.line_lang = Some(state.sub_lang);
+        }
+        return (false, false);
+    }
+
+    // ASCII lowercasing keeps byte offsets, so indices into `lower` are valid for `line`.
+    let lower = line.to_ascii_lowercase();
+    let lb = lower.as_bytes();
+    let mut host = (false, false);
+    let mut sub = (false, false);
+    let mut i = 0usize;
+
+    while i < lb.len() {
+        match state.region {
+            HtmlRegion::Script | HtmlRegion::Style => {
+                let close: &[u8] = if state.region == HtmlRegion::Script {
+                    b"</script"
+                } else {
+                    b"</style"
+                };
+                let end = (i..lb.len())
+                    .find(|&j| match_at(lb, j, close).is_some())
+                    .unwrap_or(lb.len());
+                let seg = &line[i..end];
+                if !seg.trim().is_empty() {
+                    let (c, m) = if state.sub_lang == "txt" {
+                        (true, false)
+                    } else if state.region == HtmlRegion::Script {
+                        classify_line_js_like(seg, &mut state.script_state)
+                    } else {
+                        classify_line_css_like(seg, &mut state.sub_state)
+                    };
+                    sub.0 |= c;
+                    sub.1 |= m;
+                }
+                if end == lb.len() {
+                    break;
+                }
+                // The closing tag itself is markup.
+                state.region = HtmlRegion::Markup;
+                state.sub_state = ParseState::new();
+                state.script_state = JsState::new();
+                i = end;
+            }
+            HtmlRegion::Markup => {
+                if state.in_comment {
+                    host.1 = true;
+                    match find_closing(lb, i, b"-->") {
+                        Some(end) => {
+                            state.in_comment = false;
+                            i = end;
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+
+                if let Some(region) = state.pending_tag {
+                    // Inside an opening `<script ...>` / `<style ...>` tag, possibly spanning lines.
+                    host.0 = true;
+                    let end = (i..lb.len()).find(|&j| lb[j] == b'>');
+                    let attrs = &lower[i..end.unwrap_or(lb.len())];
+                    if let Some(lang) = embedded_lang_from_attrs(region, attrs) {
+                        state.sub_lang = lang;
+                    }
+                    match end {
+                        Some(j) => {
+                            state.pending_tag = None;
+                            // `<script src="x.js" />` has no body.
+                            if j == 0 || lb[j - 1] != b'/' {
+                                state.region = region;
+                            }
+                            i = j + 1;
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+
+                if match_at(lb, i, b"<!--").is_some() {
+                    state.in_comment = true;
+                    host.1 = true;
+                    i += 4;
+                    continue;
+                }
+
+                let opened = [
+                    (b"<script".as_slice(), HtmlRegion::Script, "js"),
+                    (b"<style".as_slice(), HtmlRegion::Style, "css"),
+                ]
+                .into_iter()
+                .find(|(tag, _, _)| {
+                    match_at(lb, i, tag).is_some()
+                        && lb
+                            .get(i + tag.len())
+                            .is_none_or(|b| b.is_ascii_whitespace() || *b == b'>' || *b == b'/')
+                });
+                if let Some((tag, region, default_lang)) = opened {
+                    state.pending_tag = Some(region);
+                    state.sub_lang = default_lang;
+                    host.0 = true;
+                    i += tag.len();
+                    continue;
+                }
+
+                if !lb[i].is_ascii_whitespace() {
+                    host.0 = true;
+                }
+                i += 1;
+            }
+        }
+    }
+
+    if !host.0 && !host.1 && (sub.0 || sub.1) {
+        state.line_lang = Some(state.sub_lang);
+    }
+    (host.0 || sub.0, host.1 || sub.1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlRegion {
+    Markup,
+    Script,
+    Style,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlState {
+    pub region: HtmlRegion,
+    /// Inside a markup `<!-- -->` comment.
+    pub in_comment: bool,
+    /// An opening `<script` / `<style` tag whose `>` has not been seen yet.
+    pub pending_tag: Option<HtmlRegion>,
+    /// Language of the current (or pending) embedded region.
+    pub sub_lang: &'static str,
+    /// Block comment state of the embedded `<style>` region.
+    pub sub_state: ParseState,
+    /// State of the embedded `<script>` region.
+    pub script_state: JsState,
+    /// Embedded language of the last classified line, if it had no markup at all.
+    pub line_lang: Option<&'static str>,
+}
+
+impl Default for HtmlState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HtmlState {
+    pub fn new() -> Self {
+        Self {
+            region: HtmlRegion::Markup,
+            in_comment: false,
+            pending_tag: None,
+            sub_lang: "js",
+            sub_state: ParseState::new(),
+            script_state: JsState::new(),
+            line_lang: None,
+        }
+    }
+}
+
+/// Pick the embedded language from (lowercased) tag attributes such as `lang="ts"`.
+///
+/// Attribute names match whole words only, so `xml:lang=` / `data-lang=` are ignored.
+/// A `<script type=...>` that is not JavaScript/TypeScript (e.g. `application/ld+json`,
+/// `text/template`) holds data or markup, not script; it is reported as plain text (`"txt"`).
+fn embedded_lang_from_attrs(region: HtmlRegion, attrs: &str) -> Option<&'static str> {
+    let value_of = |name: &str| -> Option<&str> {
+        attrs.match_indices(name).find_map(|(pos, _)| {
+            let before = attrs[..pos].chars().next_back();
+            if before.is_some_and(|c| !c.is_ascii_whitespace() && c != '/') {
+                return None;
+            }
+            let rest = attrs[pos + name.len()..]
+                .trim_start()
+                .strip_prefix('=')?
+                .trim_start();
+            let rest = rest.trim_start_matches(['"', '\'']);
+            let end = rest
+                .find(|c: char| c == '"' || c == '\'' || c.is_ascii_whitespace() || c == '>')
+                .unwrap_or(rest.len());
+            Some(&rest[..end])
+        })
+    };
+
+    match region {
+        HtmlRegion::Script => {
+            if let Some(v) = value_of("lang") {
+                return Some(if v == "ts" || v == "tsx" || v.contains("typescript") {
+                    "ts"
+                } else {
+                    "js"
+                });
+            }
+            let v = value_of("type")?;
+            Some(match v {
+                _ if v.contains("typescript") => "ts",
+                "" | "module" | "text/babel" | "text/jsx" => "js",
+                _ if v.contains("javascript") || v.contains("ecmascript") => "js",
+                _ => "txt",
+            })
+        }
+        HtmlRegion::Style => match value_of("lang")? {
+            "scss" => Some("scss"),
+            "sass" => Some("sass"),
+            "less" => Some("less"),
+            _ => Some("css"),
+        },
+        HtmlRegion::Markup => None,
+    }
+}
+
+/// Markdown:
+/// - prose (and fence delimiter lines) counts as comment: it is documentation
+/// - `<!-- -->` HTML comments are comments
+/// - lines inside ```` ``` ```` / `~~~` fenced blocks are code; `state.in_fence_body` and
+///   `state.fence_tag` let callers re-classify them with the tagged language's classifier
+///   (blank lines inside a fence set `state.in_fence_body` too)
+pub fn classify_line_markdown_like(line: &str, state: &mut MarkdownState) -> (bool, bool) {
+    state.in_fence_body = false;
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        state.in_fence_body = state.fence.is_some();
+        return (false, false);
+    }
+
+    // Fence markers may be indented by up to three spaces.
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let marker = &line[indent..];
+    let fence_char = marker.as_bytes()[0];
+    let fence_len = marker.bytes().take_while(|&b| b == fence_char).count();
+    let is_fence = indent <= 3 && (fence_char == b'`' || fence_char == b'~') && fence_len >= 3;
+
+    if let Some((ch, len)) = state.fence {
+        if is_fence && fence_char == ch && fence_len >= len && marker[fence_len..].trim().is_empty()
+        {
+            state.fence = None;
+            return (false, true);
+        }
+        state.in_fence_body = true;
+        return (true, false);
+    }
+
+    // Multi-line `<!-- -->` is tracked so a fence inside one is not taken for a code block.
+    if state.in_html_comment {
+        if trimmed.contains("-->") {
+            state.in_html_comment = false;
+        }
+        return (false, true);
+    }
+
+    if is_fence {
+        state.fence = Some((fence_char, fence_len));
+        // Info string: first word, e.g. `rust` in ```` ```rust,ignore ```` or `python` in ```` ```{python} ````.
+        let info = marker[fence_len..].trim().trim_start_matches(['{', '.']);
+        let tag: String = info
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !matches!(c, ',' | '}' | '{'))
+            .collect();
+        state.fence_tag = (!tag.is_empty()).then(|| tag.to_ascii_lowercase());
+        return (false, true);
+    }
+
+    // Prose: comment, whether or not it contains `<!-- -->`.
+    if let Some(start) = trimmed.rfind("<!--") {
+        state.in_html_comment = !trimmed[start..].contains("-->");
+    }
+    (false, true)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarkdownState {
+    /// Open fence: marker character and length.
+    pub fence: Option<(u8, usize)>,
+    /// Language tag of the open (or last) fence, lowercased.
+    pub fence_tag: Option<String>,
+    /// Whether the last classified line was inside a fenced block.
+    pub in_fence_body: bool,
+    /// Inside a multi-line `<!-- -->` comment.
+    pub in_html_comment: bool,
+}
+
+impl MarkdownState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Line classifier for a `ParserKind`: the single dispatch used for whole files and by
+/// hosts that embed other languages (e.g. Markdown code fences) and only learn the
+/// language while parsing.
+#[derive(Debug, Clone)]
+pub enum Classifier {
+    CLike(ParseState),
+    Python(PythonState),
+    Lua(LuaState),
+    Xml(ParseState),
+    Css(ParseState),
+    Batch,
+    PlainText,
+    Sql(SqlState),
+    Shell(ShellState),
+    Yaml(YamlState),
+    Toml(TomlState),
+    Html(HtmlState),
+    Markdown(MarkdownState),
+    Php(PhpState),
+    Ruby(ScriptState),
+    Perl(ScriptState),
+    Haskell(NestedCommentState),
+    OCaml(NestedCommentState),
+    FSharp(NestedCommentState),
+    CNested(NestedCommentState),
+    Clojure(LispState),
+    Scheme(LispState),
+    Lisp(LispState),
+    FortranFixed,
+    FortranFree,
+    Cobol,
+    Asm(AsmState),
+    PowerShell(PowerShellState),
+    VisualBasic,
+    Hcl(HclState),
+    Dockerfile,
+    Makefile(MakefileState),
+    Nix(NixState),
+    JavaScript(JsState),
+    Cpp(ParseState),
+    Go(ParseState),
+    CSharp(ParseState),
+}
+
+impl Classifier {
+    /// Classifier with default settings for `kind` (SQL: generic dialect; assembly: GAS).
+    ///
+    /// Returns `None` for notebooks: they are JSON documents whose cells are classified
+    /// individually, not line by line.
+    pub fn for_kind(kind: ParserKind) -> Option<Self> {
+        Some(match kind {
+            ParserKind::CLike => Self::CLike(ParseState::new()),
+            ParserKind::Python => Self::Python(PythonState::new()),
+            ParserKind::Lua => Self::Lua(LuaState::new()),
+            ParserKind::Xml => Self::Xml(ParseState::new()),
+            ParserKind::Css => Self::Css(ParseState::new()),
+            ParserKind::Batch => Self::Batch,
+            ParserKind::PlainText => Self::PlainText,
+            ParserKind::Sql => Self::Sql(SqlState::default()),
+            ParserKind::Shell => Self::Shell(ShellState::new()),
+            ParserKind::Yaml => Self::Yaml(YamlState::new()),
+            ParserKind::Toml => Self::Toml(TomlState::new()),
+            ParserKind::Html => Self::Html(HtmlState::new()),
+            ParserKind::Markdown => Self::Markdown(MarkdownState::new()),
+            ParserKind::Notebook => return None,
+            ParserKind::Php => Self::Php(PhpState::new()),
+            ParserKind::Ruby => Self::Ruby(ScriptState::new()),
+            ParserKind::Perl => Self::Perl(ScriptState::new()),
+            ParserKind::Haskell => Self::Haskell(NestedCommentState::new()),
+            ParserKind::OCaml => Self::OCaml(NestedCommentState::new()),
+            ParserKind::FSharp => Self::FSharp(NestedCommentState::new()),
+            ParserKind::CNested => Self::CNested(NestedCommentState::new()),
+            ParserKind::Clojure => Self::Clojure(LispState::new()),
+            ParserKind::Scheme => Self::Scheme(LispState::new()),
+            ParserKind::Lisp => Self::Lisp(LispState::new()),
+            ParserKind::FortranFixed => Self::FortranFixed,
+            ParserKind::FortranFree => Self::FortranFree,
+            ParserKind::Cobol => Self::Cobol,
+            ParserKind::Asm => Self::Asm(AsmState::default()),
+            ParserKind::PowerShell => Self::PowerShell(PowerShellState::new()),
+            ParserKind::VisualBasic => Self::VisualBasic,
+            ParserKind::Hcl => Self::Hcl(HclState::new()),
+            ParserKind::Dockerfile => Self::Dockerfile,
+            ParserKind::Makefile => Self::Makefile(MakefileState::new()),
+            ParserKind::Nix => Self::Nix(NixState::new()),
+            ParserKind::JavaScript => Self::JavaScript(JsState::new()),
+            ParserKind::Cpp => Self::Cpp(ParseState::new()),
+            ParserKind::Go => Self::Go(ParseState::new()),
+            ParserKind::CSharp => Self::CSharp(ParseState::new()),
+        })
+    }
+
+    pub fn classify(&mut self, line: &str) -> (bool, bool) {
+        match self {
+            Self::CLike(st) => classify_line_c_like(line, st),
+            Self::Python(st) => classify_line_python_like(line, st),
+            Self::Lua(st) => classify_line_lua_like(line, st),
+            Self::Xml(st) => classify_line_xml_like(line, st),
+            Self::Css(st) => classify_line_css_like(line, st),
+            Self::Batch => classify_line_batch_like(line),
+            Self::PlainText => (!line.trim().is_empty(), false),
+            Self::Sql(st) => classify_line_sql_like(line, st),
+            Self::Shell(st) => classify_line_shell_like(line, st),
+            Self::Yaml(st) => classify_line_yaml_like(line, st),
+            Self::Toml(st) => classify_line_toml_like(line, st),
+            Self::Html(st) => classify_line_html_like(line, st),
+            Self::Markdown(st) => classify_line_markdown_like(line, st),
+            Self::Php(st) => classify_line_php_like(line, st),
+            Self::Ruby(st) => classify_line_ruby_like(line, st),
+            Self::Perl(st) => classify_line_perl_like(line, st),
+            Self::Haskell(st) => classify_line_haskell_like(line, st),
+            Self::OCaml(st) => classify_line_ocaml_like(line, st),
+            Self::FSharp(st) => classify_line_fsharp_like(line, st),
+            Self::CNested(st) => classify_line_c_nested_like(line, st),
+            Self::Clojure(st) => classify_line_clojure_like(line, st),
+            Self::Scheme(st) => classify_line_scheme_like(line, st),
+            Self::Lisp(st) => classify_line_lisp_like(line, st),
+            Self::FortranFixed => classify_line_fortran_fixed_like(line),
+            Self::FortranFree => classify_line_fortran_free_like(line),
+            Self::Cobol => classify_line_cobol_like(line),
+            Self::Asm(st) => classify_line_asm_like(line, st),
+            Self::PowerShell(st) => classify_line_powershell_like(line, st),
+            Self::VisualBasic => classify_line_vb_like(line),
+            Self::Hcl(st) => classify_line_hcl_like(line, st),
+            Self::Dockerfile => classify_line_dockerfile_like(line),
+            Self::Makefile(st) => classify_line_makefile_like(line, st),
+            Self::Nix(st) => classify_line_nix_like(line, st),
+            Self::JavaScript(st) => classify_line_js_like(line, st),
+            Self::Cpp(st) => classify_line_cpp_like(line, st),
+            Self::Go(st) => classify_line_go_like(line, st),
+            Self::CSharp(st) => classify_line_csharp_like(line, st),
+        }
+    }
+}
+
+/// PHP:
+/// - outside `<?php` / `<?=` / `<?` ... `?>` the text is inline HTML, classified with the XML rules
+/// - line comments: `//` and `#` (but not `#[` attributes); both end at `?>`
+/// - block comments: `/* */`
+/// - strings: `'...'`, `"..."`, `` `...` `` (may span lines), heredoc `<<<ID` and nowdoc
+///   `<<<'ID'`, whose bodies count as code
+pub fn classify_line_php_like(line: &str, state: &mut PhpState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut i = 0usize;
+
+    if let Some(delim) = &state.heredoc {
+        // Since PHP 7.3 the closing identifier may be indented and followed by `;`, `,`, `)`...
+        let indent = line.len() - line.trim_start().len();
+        let end = indent + delim.len();
+        if !trimmed.starts_with(delim.as_str()) || bytes.get(end).is_some_and(|&b| is_ident_byte(b))
+        {
+            return (true, false);
+        }
+        state.heredoc = None;
+        i = end;
+    }
+
+    let mut saw_code = i > 0;
+    let mut saw_comment = false;
+
+    while i < bytes.len() {
+        if !state.in_php {
+            let open = (i..bytes.len()).find(|&j| match_at(bytes, j, b"<?").is_some());
+            let end = open.unwrap_or(bytes.len());
+            let seg = &line[i..end];
+            if !seg.trim().is_empty() {
+                let (c, m) = classify_line_xml_like(seg, &mut state.markup);
+                saw_code |= c;
+                saw_comment |= m;
+            }
+            let Some(open) = open else {
+                break;
+            };
+            state.in_php = true;
+            saw_code = true;
+            i = open + 2;
+            let rest = &bytes[i..];
+            if rest.len() >= 3 && rest[..3].eq_ignore_ascii_case(b"php") {
+                i += 3;
+            } else if rest.first() == Some(&b'=') {
+                i += 1;
+            }
+            continue;
+        }
+
+        if state.in_block_comment {
+            saw_comment = true;
+            match find_closing(bytes, i, b"*/") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        if let Some(q) = state.in_quote {
+            saw_code = true;
+            match scan_quoted(bytes, i, q) {
+                Some(end) => {
+                    state.in_quote = None;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let b = bytes[i];
+        if match_at(bytes, i, b"?>").is_some() {
+            state.in_php = false;
+            saw_code = true;
+            i += 2;
+            continue;
+        }
+        if match_at(bytes, i, b"/*").is_some() {
+            state.in_block_comment = true;
+            saw_comment = true;
+            i += 2;
+            continue;
+        }
+        if match_at(bytes, i, b"//").is_some() || (b == b'#' && bytes.get(i + 1) != Some(&b'[')) {
+            saw_comment = true;
+            // A line comment ends at the closing tag.
+            match (i..bytes.len()).find(|&j| match_at(bytes, j, b"?>").is_some()) {
+                Some(close) => {
+                    i = close;
+                    continue;
+                }
+                None => break,
+            }
+        }
+        if matches!(b, b'\'' | b'"' | b'`') {
+            state.in_quote = Some(b);
+            saw_code = true;
+            i += 1;
+            continue;
+        }
+        if match_at(bytes, i, b"<<<").is_some()
+            && let Some((delim, end)) = php_heredoc_start(bytes, i + 3)
+        {
+            state.heredoc = Some(delim);
+            saw_code = true;
+            i = end;
+            continue;
+        }
+
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhpState {
+    /// Inside `<?php ... ?>`; otherwise the text is inline HTML.
+    pub in_php: bool,
+    pub in_block_comment: bool,
+    /// Open quote of a string continuing across lines.
+    pub in_quote: Option<u8>,
+    /// Closing identifier of an open heredoc / nowdoc.
+    pub heredoc: Option<String>,
+    /// Comment state of the inline HTML.
+    pub markup: ParseState,
+}
+
+impl PhpState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Parse the identifier after `<<<` (`ID`, `"ID"` or `'ID'`); returns it and the index after it.
+fn php_heredoc_start(bytes: &[u8], mut i: usize) -> Option<(String, usize)> {
+    while i < bytes.len() && (bytes[i] == b' ' || bytes[i] == b'\t') {
+        i += 1;
+    }
+    let quote = bytes.get(i).copied().filter(|&b| b == b'\'' || b == b'"');
+    if quote.is_some() {
+        i += 1;
+    }
+    let start = i;
+    while i < bytes.len() && is_ident_byte(bytes[i]) {
+        i += 1;
+    }
+    if i == start || bytes[start].is_ascii_digit() {
+        return None;
+    }
+    let delim = String::from_utf8_lossy(&bytes[start..i]).into_owned();
+    if let Some(q) = quote {
+        if bytes.get(i) != Some(&q) {
+            return None;
+        }
+        i += 1;
+    }
+    Some((delim, i))
+}
+
+/// Ruby:
+/// - line comment: `#` outside strings (`"#{x}"` interpolation is part of the string)
+/// - `/.../` after `=~` / `!~` is a regex, so a `#` in it is code
+/// - `=begin` ... `=end` at column 0: embedded documentation, counted as comment
+/// - heredocs: `<<ID`, `<<-ID`, `<<~ID`, optionally quoted; bodies count as code
+/// - everything after `__END__` is data and counted as comment
+pub fn classify_line_ruby_like(line: &str, state: &mut ScriptState) -> (bool, bool) {
+    classify_line_script(line, state, ScriptDialect::Ruby)
+}
+
+/// Perl:
+/// - line comment: `#` outside strings (`$#array` is code)
+/// - quote-like operators `s///`, `tr///`, `y///`, `m//`, `qr//`, `q//`, `qq//`, `qw//`, `qx//`
+///   (any delimiter, including bracket pairs) are code, so `s/#.*//` has no comment; they are
+///   only tracked within one line; so is a bare `/.../` after `=~` / `!~`
+/// - POD: a line starting with `=word` (`=pod`, `=head1`, ...) at column 0 opens a block that
+///   runs until `=cut`; counted as comment
+/// - heredocs: `<<ID`, `<<~ID`, `<<"ID"`, `<<'ID'`; bodies count as code
+/// - everything after `__END__` / `__DATA__` is counted as comment
+pub fn classify_line_perl_like(line: &str, state: &mut ScriptState) -> (bool, bool) {
+    classify_line_script(line, state, ScriptDialect::Perl)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScriptDialect {
+    Ruby,
+    Perl,
+}
+
+fn classify_line_script(
+    line: &str,
+    state: &mut ScriptState,
+    dialect: ScriptDialect,
+) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+    if state.in_data {
+        return (false, true);
+    }
+
+    if state.in_doc {
+        let end = match dialect {
+            ScriptDialect::Ruby => "=end",
+            ScriptDialect::Perl => "=cut",
+        };
+        if starts_with_word(line, end) {
+            state.in_doc = false;
+        }
+        return (false, true);
+    }
+
+    // Heredoc body: read verbatim until the terminator line.
+    if let Some(doc) = state.heredocs.first() {
+        let body = if doc.indented {
+            trimmed
+        } else {
+            line.trim_end()
+        };
+        if body == doc.delim {
+            state.heredocs.remove(0);
+        }
+        return (true, false);
+    }
+
+    if state.in_quote.is_none() {
+        let data_markers: &[&str] = match dialect {
+            ScriptDialect::Ruby => &["__END__"],
+            ScriptDialect::Perl => &["__END__", "__DATA__"],
+        };
+        if data_markers.contains(&line.trim_end()) {
+            state.in_data = true;
+            return (false, true);
+        }
+        let doc_start = match dialect {
+            ScriptDialect::Ruby => starts_with_word(line, "=begin"),
+            ScriptDialect::Perl => {
+                line.starts_with('=') && line.as_bytes().get(1).is_some_and(u8::is_ascii_alphabetic)
+            }
+        };
+        if doc_start {
+            // A Perl `=cut` with no open block is still documentation.
+            state.in_doc = !(dialect == ScriptDialect::Perl && line.starts_with("=cut"));
+            return (false, true);
+        }
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if let Some(q) = state.in_quote {
+            saw_code = true;
+            match scan_quoted(bytes, i, q) {
+                Some(end) => {
+                    state.in_quote = None;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let b = bytes[i];
+        match b {
+            b'#' => {
+                saw_comment = true;
+                break;
+            }
+            b'=' | b'!' if bytes.get(i + 1) == Some(&b'~') => {
+                // Match operator: a `/.../` operand is a regex, so `x =~ /#/` has no comment.
+                saw_code = true;
+                i += 2;
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                if bytes.get(i) == Some(&b'/') {
+                    i = scan_quoted(bytes, i + 1, b'/').unwrap_or(bytes.len());
+                }
+                continue;
+            }
+            b'$' if dialect == ScriptDialect::Perl && bytes.get(i + 1) == Some(&b'#') => {
+                saw_code = true;
+                i += 2;
+                continue;
+            }
+            b'a'..=b'z' if dialect == ScriptDialect::Perl => {
+                if let Some(end) = perl_quote_like_end(bytes, i) {
+                    saw_code = true;
+                    i = end;
+                    continue;
+                }
+            }
+            b'\'' | b'"' | b'`' => {
+                state.in_quote = Some(b);
+                saw_code = true;
+                i += 1;
+                continue;
+            }
+            b'<' if match_at(bytes, i, b"<<").is_some() => {
+                saw_code = true;
+                if let Some((doc, end)) = script_heredoc_start(bytes, i + 2, dialect) {
+                    state.heredocs.push(doc);
+                    i = end;
+                } else {
+                    i += 2;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+/// End of a Perl quote-like operator (`s{a}{b}`, `tr/a/b/`, `qr#x#`, ...) starting at `i`, or
+/// `None` if there is none. Parts that are not closed on this line run to its end.
+fn perl_quote_like_end(bytes: &[u8], i: usize) -> Option<usize> {
+    // Not part of a longer word, a variable (`$s`), a method (`->s`) or a file test (`-s`).
+    if i > 0 && (is_ident_byte(bytes[i - 1]) || b"$@%&*->".contains(&bytes[i - 1])) {
+        return None;
+    }
+    let word_end = i + bytes[i..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphabetic())
+        .count();
+    let parts = match &bytes[i..word_end] {
+        b"s" | b"tr" | b"y" => 2,
+        b"m" | b"qr" | b"q" | b"qq" | b"qw" | b"qx" => 1,
+        _ => return None,
+    };
+
+    let skip_space = |mut j: usize| {
+        while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+            j += 1;
+        }
+        j
+    };
+    let mut j = skip_space(word_end);
+    let delim = *bytes.get(j)?;
+    // `s #...` is a comment; `y => 1`, `q,` and the like are barewords.
+    if (delim == b'#' && j > word_end)
+        || delim.is_ascii_alphanumeric()
+        || b"_=,;)}>".contains(&delim)
+    {
+        return None;
+    }
+
+    let closer = |open: u8| match open {
+        b'(' => b')',
+        b'[' => b']',
+        b'{' => b'}',
+        b'<' => b'>',
+        _ => open,
+    };
+    let mut open = delim;
+    for part in 0..parts {
+        if part > 0 && closer(delim) != delim {
+            // Bracketed: the replacement has its own delimiters, e.g. `s{a} {b}`.
+            j = skip_space(j);
+            open = *bytes.get(j)?;
+        } else if part > 0 {
+            // `s/a/b/`: the closing delimiter of the pattern also opens the replacement.
+            j -= 1;
+        }
+        let close = closer(open);
+        let mut depth = 0usize;
+        j += 1;
+        loop {
+            let Some(&b) = bytes.get(j) else {
+                return Some(bytes.len());
+            };
+            j += 1;
+            if b == b'\\' {
+                j += 1;
+            } else if b == close && depth == 0 {
+                break;
+            } else if b == close {
+                depth -= 1;
+            } else if b == open && open != close {
+                depth += 1;
+            }
+        }
+    }
+    Some(j)
+}
+
+/// Whether `line` starts with `word` followed by whitespace or the end of the line.
+fn starts_with_word(line: &str, word: &str) -> bool {
+    line.strip_prefix(word)
+        .is_some_and(|rest| rest.chars().next().is_none_or(char::is_whitespace))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptHeredoc {
+    /// Terminator word, with quotes removed.
+    pub delim: String,
+    /// `<<-` / `<<~`: the terminator may be indented.
+    pub indented: bool,
+}
+
+/// State for the Ruby and Perl classifiers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptState {
+    /// Inside `=begin`/`=end` (Ruby) or POD (Perl).
+    pub in_doc: bool,
+    /// After `__END__` / `__DATA__`.
+    pub in_data: bool,
+    /// Quote still open at the end of the previous line.
+    pub in_quote: Option<u8>,
+    /// Heredocs whose bodies are pending; the first one is being read.
+    pub heredocs: Vec<ScriptHeredoc>,
+}
+
+impl ScriptState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Parse a heredoc terminator after `<<` (at `i`). Bare terminators must start with an uppercase
+/// letter or `_`, so shifts and appends like `a <<b` are not mistaken for heredocs.
+fn script_heredoc_start(
+    bytes: &[u8],
+    mut i: usize,
+    dialect: ScriptDialect,
+) -> Option<(ScriptHeredoc, usize)> {
+    let indented = match bytes.get(i) {
+        Some(b'~') => true,
+        Some(b'-') => dialect == ScriptDialect::Ruby,
+        _ => false,
+    };
+    if indented {
+        i += 1;
+    }
+
+    match bytes.get(i).copied()? {
+        q @ (b'\'' | b'"' | b'`') => {
+            let start = i + 1;
+            let end = start + bytes[start..].iter().position(|&b| b == q)?;
+            if end == start {
+                return None;
+            }
+            let delim = String::from_utf8_lossy(&bytes[start..end]).into_owned();
+            Some((ScriptHeredoc { delim, indented }, end + 1))
+        }
+        b if b.is_ascii_uppercase() || b == b'_' => {
+            let start = i;
+            while i < bytes.len() && is_ident_byte(bytes[i]) {
+                i += 1;
+            }
+            let delim = String::from_utf8_lossy(&bytes[start..i]).into_owned();
+            Some((ScriptHeredoc { delim, indented }, i))
+        }
+        _ => None,
+    }
+}
+
+/// Haskell / Elm:
+/// - line comment: `--` (a run of two or more dashes not followed by another operator
+///   character, so `-->` is an operator)
+/// - block comments: `{- -}`, which nest; `{-# ... #-}` pragmas are code
+/// - strings: `"..."` with backslash escapes (`'` is left alone, it is also an identifier char)
+pub fn classify_line_haskell_like(line: &str, state: &mut NestedCommentState) -> (bool, bool) {
+    classify_line_nested(line, state, NestedSyntax::Haskell)
+}
+
+/// OCaml: `(* *)` block comments, which nest. There is no line comment.
+pub fn classify_line_ocaml_like(line: &str, state: &mut NestedCommentState) -> (bool, bool) {
+    classify_line_nested(line, state, NestedSyntax::OCaml)
+}
+
+/// F#: `//` line comments and nesting `(* *)` block comments; `(*)` is the multiplication
+/// operator, not a comment.
+pub fn classify_line_fsharp_like(line: &str, state: &mut NestedCommentState) -> (bool, bool) {
+    classify_line_nested(line, state, NestedSyntax::FSharp)
+}
+
+/// C-like with nesting `/* */` block comments, as in Swift, Kotlin, Scala and Dart.
+/// `"""` (and Dart `'''`) multi-line strings are code, even where they contain `//` or `/*`.
+pub fn classify_line_c_nested_like(line: &str, state: &mut NestedCommentState) -> (bool, bool) {
+    classify_line_nested(line, state, NestedSyntax::CLike)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NestedSyntax {
+    Haskell,
+    OCaml,
+    FSharp,
+    CLike,
+}
+
+impl NestedSyntax {
+    fn block(self) -> (&'static [u8], &'static [u8]) {
+        match self {
+            NestedSyntax::Haskell => (b"{-", b"-}"),
+            NestedSyntax::OCaml | NestedSyntax::FSharp => (b"(*", b"*)"),
+            NestedSyntax::CLike => (b"/*", b"*/"),
+        }
+    }
+
+    fn quotes(self) -> &'static [u8] {
+        match self {
+            NestedSyntax::CLike => b"\"'",
+            _ => b"\"",
+        }
+    }
+}
+
+fn classify_line_nested(
+    line: &str,
+    state: &mut NestedCommentState,
+    syntax: NestedSyntax,
+) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let (open, close) = syntax.block();
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if let Some(q) = state.triple_quote {
+            saw_code = true;
+            match scan_triple_quoted(bytes, i, q) {
+                Some(end) => {
+                    state.triple_quote = None;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+        if state.depth > 0 {
+            saw_comment = true;
+            if match_at(bytes, i, close).is_some() {
+                state.depth -= 1;
+                i += close.len();
+            } else if match_at(bytes, i, open).is_some() {
+                state.depth += 1;
+                i += open.len();
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+
+        let b = bytes[i];
+        if syntax == NestedSyntax::Haskell && match_at(bytes, i, b"{-#").is_some() {
+            // Pragma, e.g. `{-# LANGUAGE GADTs #-}`.
+            saw_code = true;
+            i = find_closing(bytes, i + 3, b"#-}").unwrap_or(bytes.len());
+            continue;
+        }
+        if syntax == NestedSyntax::FSharp && match_at(bytes, i, b"(*)").is_some() {
+            saw_code = true;
+            i += 3;
+            continue;
+        }
+        if match_at(bytes, i, open).is_some() {
+            state.depth = 1;
+            saw_comment = true;
+            i += open.len();
+            continue;
+        }
+
+        match syntax {
+            NestedSyntax::Haskell if match_at(bytes, i, b"--").is_some() => {
+                let dashes = bytes[i..].iter().take_while(|&&c| c == b'-').count();
+                if !bytes.get(i + dashes).is_some_and(|&c| is_haskell_symbol(c)) {
+                    saw_comment = true;
+                    break;
+                }
+                saw_code = true;
+                i += dashes;
+                continue;
+            }
+            NestedSyntax::FSharp | NestedSyntax::CLike if match_at(bytes, i, b"//").is_some() => {
+                saw_comment = true;
+                break;
+            }
+            _ => {}
+        }
+
+        if syntax == NestedSyntax::CLike
+            && (b == b'"' || b == b'\'')
+            && match_at(bytes, i, &[b; 3]).is_some()
+        {
+            saw_code = true;
+            state.triple_quote = Some(b);
+            i += 3;
+            continue;
+        }
+        if syntax.quotes().contains(&b) {
+            saw_code = true;
+            match scan_quoted(bytes, i + 1, b) {
+                Some(end) => {
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+/// Scan a `"""` / `'''` string body from `i`; returns the index after the closing delimiter.
+/// Quotes directly before the delimiter belong to the content (Kotlin `""""a""""`).
+fn scan_triple_quoted(bytes: &[u8], mut i: usize, quote: u8) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote && match_at(bytes, i, &[quote; 3]).is_some() => {
+                let run = bytes[i..].iter().take_while(|&&c| c == quote).count();
+                return Some(i + run);
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Haskell operator characters; `--` followed by one of these is an operator, not a comment.
+fn is_haskell_symbol(b: u8) -> bool {
+    b"!#$%&*+./<=>?@\\^|~:".contains(&b)
+}
+
+/// Block comment state for languages whose block comments nest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NestedCommentState {
+    /// Number of currently open block comments.
+    pub depth: u32,
+    /// Quote byte of a `"""` / `'''` string still open at the end of the previous line.
+    pub triple_quote: Option<u8>,
+}
+
+impl NestedCommentState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Clojure:
+/// - line comment: `;`
+/// - `#_` discards the next form, and `(comment ...)` forms are never evaluated; both count as
+///   comment, across lines
+/// - strings: `"..."` with backslash escapes, which may span lines; `\;` is a character literal
+pub fn classify_line_clojure_like(line: &str, state: &mut LispState) -> (bool, bool) {
+    classify_line_lisp_family(line, state, LispDialect::Clojure)
+}
+
+/// Scheme / Racket: `;`, nesting `#| |#`, and `#;` datum comments, which discard the next form.
+pub fn classify_line_scheme_like(line: &str, state: &mut LispState) -> (bool, bool) {
+    classify_line_lisp_family(line, state, LispDialect::Scheme)
+}
+
+/// Common Lisp / Emacs Lisp: `;` and nesting `#| |#`; `#\;` and `?;` are character literals.
+pub fn classify_line_lisp_like(line: &str, state: &mut LispState) -> (bool, bool) {
+    classify_line_lisp_family(line, state, LispDialect::Lisp)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LispDialect {
+    Clojure,
+    Scheme,
+    Lisp,
+}
+
+fn classify_line_lisp_family(
+    line: &str,
+    state: &mut LispState,
+    dialect: LispDialect,
+) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if state.block_depth > 0 {
+            saw_comment = true;
+            if match_at(bytes, i, b"|#").is_some() {
+                state.block_depth -= 1;
+                i += 2;
+            } else if match_at(bytes, i, b"#|").is_some() {
+                state.block_depth += 1;
+                i += 2;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+
+        if state.in_string {
+            if state.discard.is_some() {
+                saw_comment = true;
+            } else {
+                saw_code = true;
+            }
+            match scan_quoted(bytes, i, b'"') {
+                Some(end) => {
+                    state.in_string = false;
+                    i = end;
+                    if state.discard == Some(0) {
+                        state.discard = None;
+                    }
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let b = bytes[i];
+        if b.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if b == b';' {
+            saw_comment = true;
+            break;
+        }
+        if match_at(bytes, i, b"#|").is_some() {
+            state.block_depth = 1;
+            saw_comment = true;
+            i += 2;
+            continue;
+        }
+
+        if let Some(depth) = state.discard {
+            saw_comment = true;
+            match b {
+                b'"' => {
+                    state.in_string = true;
+                    i += 1;
+                }
+                b'(' | b'[' | b'{' => {
+                    state.discard = Some(depth + 1);
+                    i += 1;
+                }
+                b')' | b']' | b'}' if depth > 0 => {
+                    state.discard = (depth > 1).then(|| depth - 1);
+                    i += 1;
+                }
+                // A closing bracket while still waiting for the form ends the discard.
+                b')' | b']' | b'}' => state.discard = None,
+                // Reader prefixes such as `'x`, `#{...}` or `@x` belong to the discarded form.
+                b'\'' | b'`' | b'~' | b'@' | b'^' | b'#' if depth == 0 => i += 1,
+                b'\\' => i += 2,
+                _ => {
+                    while i < bytes.len() && !is_lisp_delimiter(bytes[i]) {
+                        i += 1;
+                    }
+                    if depth == 0 {
+                        state.discard = None;
+                    }
+                }
+            }
+            continue;
+        }
+
+        let discard_start = match dialect {
+            LispDialect::Clojure => match_at(bytes, i, b"#_").is_some(),
+            LispDialect::Scheme => match_at(bytes, i, b"#;").is_some(),
+            LispDialect::Lisp => false,
+        };
+        if discard_start {
+            state.discard = Some(0);
+            saw_comment = true;
+            i += 2;
+            continue;
+        }
+        if dialect == LispDialect::Clojure
+            && match_at(bytes, i, b"(comment").is_some()
+            && bytes.get(i + 8).is_none_or(|&c| is_lisp_delimiter(c))
+        {
+            state.discard = Some(1);
+            saw_comment = true;
+            i += 8;
+            continue;
+        }
+
+        saw_code = true;
+        match b {
+            b'"' => {
+                state.in_string = true;
+                i += 1;
+            }
+            // Character literals: `\;` (Clojure), `#\;` (Common Lisp, Scheme).
+            b'\\' => i += 2,
+            // Emacs Lisp character literals: `?;`, `?\;`.
+            b'?' if dialect == LispDialect::Lisp && (i == 0 || is_lisp_delimiter(bytes[i - 1])) => {
+                let escaped = bytes.get(i + 1) == Some(&b'\\');
+                i += if escaped { 3 } else { 2 };
+            }
+            _ => i += 1,
+        }
+    }
+
+    (saw_code, saw_comment)
+}
+
+/// Bytes that end a Lisp symbol or number.
+fn is_lisp_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace() || b"()[]{}\";".contains(&b)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LispState {
+    /// Number of open `#| |#` block comments.
+    pub block_depth: u32,
+    /// Inside a string continuing across lines.
+    pub in_string: bool,
+    /// Inside a discarded form (`#_`, `#;`, `(comment ...)`): its bracket depth, with `Some(0)`
+    /// meaning the form has not started yet.
+    pub discard: Option<u32>,
+}
+
+impl LispState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Fixed-form Fortran (FORTRAN 77 style). Columns matter, so the line is not trimmed:
+/// - `C`, `c`, `*` or `!` in column 1 makes the whole line a comment
+/// - columns 1-5 hold the label and column 6 the continuation mark (`!` there is not a comment)
+/// - from column 7, `!` outside `'...'` / `"..."` starts a trailing comment
+/// - a tab within the first six columns ends the label field (DEC tab format)
+pub fn classify_line_fortran_fixed_like(line: &str) -> (bool, bool) {
+    if line.trim().is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    if matches!(bytes[0], b'C' | b'c' | b'*' | b'!') {
+        return (false, true);
+    }
+
+    let stmt_start = match bytes.iter().take(6).position(|&b| b == b'\t') {
+        // After a tab, a digit is the continuation mark.
+        Some(tab) if bytes.get(tab + 1).is_some_and(u8::is_ascii_digit) => tab + 2,
+        Some(tab) => tab + 1,
+        None => bytes.len().min(6),
+    };
+    let label_code = bytes[..stmt_start].iter().any(|b| !b.is_ascii_whitespace());
+    let (code, comment) = fortran_statement(&bytes[stmt_start..]);
+    (label_code || code, comment)
+}
+
+/// Free-form Fortran (Fortran 90 and later): `!` outside strings starts a comment.
+pub fn classify_line_fortran_free_like(line: &str) -> (bool, bool) {
+    if line.trim().is_empty() {
+        return (false, false);
+    }
+    fortran_statement(line.as_bytes())
+}
+
+/// Classify Fortran statement text: code before a `!` outside strings, comment after it.
+/// Quotes are escaped by doubling (`'it''s'`), which toggling in and out handles naturally.
+fn fortran_statement(bytes: &[u8]) -> (bool, bool) {
+    let mut saw_code = false;
+    let mut quote: Option<u8> = None;
+    for &b in bytes {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if b == b'!' => return (saw_code, true),
+            None if b == b'\'' || b == b'"' => {
+                quote = Some(b);
+                saw_code = true;
+            }
+            None if !b.is_ascii_whitespace() => saw_code = true,
+            None => {}
+        }
+    }
+    (saw_code, false)
+}
+
+/// COBOL (fixed reference format). Columns matter, so the line is not trimmed:
+/// - columns 1-6 are the sequence number area and columns 73+ the identification area;
+///   neither is counted, so a line with nothing else is neither code nor comment (blank)
+/// - `*` or `/` in the indicator column (7) makes the line a comment
+/// - in the program text (columns 8-72), `*>` outside quotes starts a trailing comment
+pub fn classify_line_cobol_like(line: &str) -> (bool, bool) {
+    if line.trim().is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    match bytes.get(6) {
+        Some(b'*' | b'/') => return (false, true),
+        Some(_) => {}
+        None => return (false, false),
+    }
+
+    let text = &bytes[7..bytes.len().min(72)];
+    let mut saw_code = false;
+    let mut quote: Option<u8> = None;
+    let mut i = 0usize;
+    while i < text.len() {
+        let b = text[i];
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if match_at(text, i, b"*>").is_some() => return (saw_code, true),
+            None if b == b'\'' || b == b'"' => {
+                quote = Some(b);
+                saw_code = true;
+            }
+            None if !b.is_ascii_whitespace() => saw_code = true,
+            None => {}
+        }
+        i += 1;
+    }
+    (saw_code, false)
+}
+
+/// Assembly, per dialect:
+/// - GAS (x86 and others): `#` and `//` line comments, `/* */` block comments
+/// - ARM (GNU as): `@` and `//` line comments, `/* */`, `#` only at line start (elsewhere it
+///   prefixes an immediate, e.g. `mov r0, #1`)
+/// - NASM / MASM: `;` line comments
+///
+/// Strings (`"..."`, plus `'...'` and `` `...` `` in NASM) are skipped. With
+/// `state.preprocessed` (`.S` files), `#include`, `#define` and other C preprocessor
+/// directives are code.
+pub fn classify_line_asm_like(line: &str, state: &mut AsmState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = line.len() - line.trim_start().len();
+    let line_start = i;
+
+    if state.preprocessed && !state.in_block_comment && is_cpp_directive(trimmed) {
+        saw_code = true;
+        i += 1;
+    }
+
+    let dialect = state.dialect;
+    while i < bytes.len() {
+        if state.in_block_comment {
+            saw_comment = true;
+            match find_closing(bytes, i, b"*/") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let b = bytes[i];
+        let is_quote = b == b'"' || (dialect == AsmDialect::Nasm && (b == b'\'' || b == b'`'));
+        if is_quote {
+            saw_code = true;
+            match scan_quoted(bytes, i + 1, b) {
+                Some(end) => {
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let line_comment = match dialect {
+            AsmDialect::Gas => b == b'#' || match_at(bytes, i, b"//").is_some(),
+            AsmDialect::Arm => {
+                b == b'@' || (b == b'#' && i == line_start) || match_at(bytes, i, b"//").is_some()
+            }
+            AsmDialect::Nasm => b == b';',
+        };
+        if line_comment {
+            saw_comment = true;
+            break;
+        }
+        if dialect != AsmDialect::Nasm && match_at(bytes, i, b"/*").is_some() {
+            state.in_block_comment = true;
+            saw_comment = true;
+            i += 2;
+            continue;
+        }
+
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+/// Whether a trimmed line is a C preprocessor directive such as `#include` or `# define`.
+fn is_cpp_directive(trimmed: &str) -> bool {
+    const DIRECTIVES: &[&str] = &[
+        "include", "define", "undef", "if", "ifdef", "ifndef", "elif", "else", "endif", "error",
+        "warning", "pragma", "line",
+    ];
+    let Some(rest) = trimmed.strip_prefix('#') else {
+        return false;
+    };
+    let word: String = rest
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect();
+    DIRECTIVES.contains(&word.as_str())
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AsmDialect {
+    /// GNU as syntax (AT&T x86 and most other targets).
+    #[default]
+    Gas,
+    /// GNU as for ARM / AArch64.
+    Arm,
+    /// NASM, YASM and MASM-style `;` comments.
+    Nasm,
+}
+
+impl AsmDialect {
+    /// How many non-blank lines `detect` looks at.
+    const DETECT_LINES: usize = 200;
+
+    /// Guess the dialect from file content: `;` comments and NASM directives, or ARM
+    /// `@` comments, `.syntax`/`.thumb` directives and `#` immediates. Defaults to GAS.
+    pub fn detect(content: &str) -> Self {
+        const NASM_DIRECTIVES: &[&str] = &[
+            "%define",
+            "%include",
+            "%macro",
+            "section .",
+            "bits ",
+            "global ",
+        ];
+        const ARM_DIRECTIVES: &[&str] = &[".syntax", ".thumb", ".arm", ".cpu", ".fpu"];
+
+        let (mut gas, mut arm, mut nasm) = (0u32, 0u32, 0u32);
+        for line in content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .take(Self::DETECT_LINES)
+        {
+            let lower = line.to_ascii_lowercase();
+            if line.starts_with(';') {
+                nasm += 1;
+            }
+            if NASM_DIRECTIVES.iter().any(|p| lower.starts_with(p)) {
+                nasm += 2;
+            }
+            if line.starts_with('@') {
+                arm += 1;
+            }
+            if ARM_DIRECTIVES.iter().any(|p| lower.starts_with(p)) {
+                arm += 2;
+            }
+            if line.contains(", #") || line.contains(",#") {
+                arm += 1;
+            }
+            if line.starts_with('#') || line.starts_with("/*") || line.contains('%') {
+                gas += 1;
+            }
+        }
+
+        if nasm > gas && nasm >= arm {
+            AsmDialect::Nasm
+        } else if arm > gas {
+            AsmDialect::Arm
+        } else {
+            AsmDialect::Gas
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AsmState {
+    pub dialect: AsmDialect,
+    /// The file runs through the C preprocessor first (`.S`).
+    pub preprocessed: bool,
+    pub in_block_comment: bool,
+}
+
+impl AsmState {
+    pub fn new(dialect: AsmDialect, preprocessed: bool) -> Self {
+        Self {
+            dialect,
+            preprocessed,
+            in_block_comment: false,
+        }
+    }
+}
+
+/// PowerShell:
+/// - line comment: `#` at the start of a token (`a#b` is a bare word)
+/// - block comments: `<# #>`
+/// - strings: `'...'` (`''` escape) and `"..."` (backtick escapes), which may span lines
+/// - here-strings: `@"` / `@'` at the end of a line, closed by `"@` / `'@` at the start of a
+///   line; bodies count as code
+pub fn classify_line_powershell_like(line: &str, state: &mut PowerShellState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut i = 0usize;
+
+    if let Some(q) = state.here_string {
+        if !(trimmed.as_bytes()[0] == q && trimmed.as_bytes().get(1) == Some(&b'@')) {
+            return (true, false);
+        }
+        state.here_string = None;
+        i = line.len() - line.trim_start().len() + 2;
+    }
+
+    let mut saw_code = i > 0;
+    let mut saw_comment = false;
+
+    while i < bytes.len() {
+        if state.in_block_comment {
+            saw_comment = true;
+            match find_closing(bytes, i, b"#>") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        if let Some(q) = state.in_quote {
+            saw_code = true;
+            match scan_powershell_quote(bytes, i, q) {
+                Some(end) => {
+                    state.in_quote = None;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let b = bytes[i];
+        if match_at(bytes, i, b"<#").is_some() {
+            state.in_block_comment = true;
+            saw_comment = true;
+            i += 2;
+            continue;
+        }
+        let token_start =
+            i == 0 || bytes[i - 1].is_ascii_whitespace() || b";(){}|&,=".contains(&bytes[i - 1]);
+        if b == b'#' && token_start {
+            saw_comment = true;
+            break;
+        }
+        if b == b'@'
+            && let Some(&q) = bytes.get(i + 1)
+            && (q == b'"' || q == b'\'')
+            && line[i + 2..].trim().is_empty()
+        {
+            state.here_string = Some(q);
+            saw_code = true;
+            break;
+        }
+        if b == b'\'' || b == b'"' {
+            state.in_quote = Some(b);
+            saw_code = true;
+            i += 1;
+            continue;
+        }
+
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerShellState {
+    pub in_block_comment: bool,
+    /// Quote of a string continuing across lines.
+    pub in_quote: Option<u8>,
+    /// Quote of an open here-string (`@"` or `@'`).
+    pub here_string: Option<u8>,
+}
+
+impl PowerShellState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Scan a PowerShell string body from `i`; returns the index after the closing quote.
+/// Doubled quotes are handled by closing and reopening; `"..."` also has backtick escapes.
+fn scan_powershell_quote(bytes: &[u8], mut i: usize, quote: u8) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' if quote == b'"' => i += 2,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Visual Basic / VBScript:
+/// - line comment: `'` outside `"..."` strings (`""` is an escaped quote), including `'''` docs
+/// - `REM` (case-insensitive) at the start of a statement, i.e. at line start or after `:`
+pub fn classify_line_vb_like(line: &str) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut in_string = false;
+    let mut statement_start = true;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_string {
+            in_string = b != b'"';
+            i += 1;
+            continue;
+        }
+        if b == b'\'' {
+            return (saw_code, true);
+        }
+        if b.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if statement_start
+            && bytes.len() >= i + 3
+            && bytes[i..i + 3].eq_ignore_ascii_case(b"rem")
+            && bytes.get(i + 3).is_none_or(u8::is_ascii_whitespace)
+        {
+            return (saw_code, true);
+        }
+
+        saw_code = true;
+        statement_start = b == b':';
+        in_string = b == b'"';
+        i += 1;
+    }
+
+    (saw_code, false)
+}
+
+/// HCL / Terraform:
+/// - line comments: `#` and `//`; block comments: `/* */`
+/// - strings: `"..."` with backslash escapes
+/// - heredocs: `<<EOT` / `<<-EOT`, closed by the identifier alone on a line; bodies count as code
+pub fn classify_line_hcl_like(line: &str, state: &mut HclState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    if let Some(delim) = &state.heredoc {
+        if trimmed == delim {
+            state.heredoc = None;
+        }
+        return (true, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if state.in_block_comment {
+            saw_comment = true;
+            match find_closing(bytes, i, b"*/") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let b = bytes[i];
+        if b == b'#' || match_at(bytes, i, b"//").is_some() {
+            saw_comment = true;
+            break;
+        }
+        if match_at(bytes, i, b"/*").is_some() {
+            state.in_block_comment = true;
+            saw_comment = true;
+            i += 2;
+            continue;
+        }
+        if b == b'"' {
+            saw_code = true;
+            match scan_quoted(bytes, i + 1, b'"') {
+                Some(end) => {
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
+        }
+        if match_at(bytes, i, b"<<").is_some() {
+            let start = i + 2 + usize::from(bytes.get(i + 2) == Some(&b'-'));
+            let end = (start..bytes.len())
+                .find(|&j| !is_ident_byte(bytes[j]))
+                .unwrap_or(bytes.len());
+            if end > start && line[end..].trim().is_empty() {
+                state.heredoc = Some(line[start..end].to_string());
+                saw_code = true;
+                break;
+            }
+        }
+
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
+        i += 1;
+    }
+
+    (saw_code, saw_comment)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HclState {
+    pub in_block_comment: bool,
+    /// Closing identifier of an open heredoc.
+    pub heredoc: Option<String>,
+}
+
+impl HclState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Dockerfile: only a `#` at the start of a line is a comment (including `# syntax=` parser
+/// directives); elsewhere `#` is an argument, e.g. `RUN echo #1`.
+pub fn classify_line_dockerfile_like(line: &str) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+    if trimmed.starts_with('#') {
+        (false, true)
+    } else {
+        (true, false)
+    }
+}
+
+/// Makefile:
+/// - make lines: `#` starts a comment unless escaped as `\#`
+/// - recipe lines (tab-indented lines after a rule, and their `\` continuations) are shell
+///   commands and use the shell rules, so `echo "a#b"` is code and `\t# note` is a comment
+/// - conditionals (`ifeq`, `else`, `endif`, ...) and comments do not end a rule's recipe
+/// - `define NAME` ... `endef` bodies are literal text (code), even where they contain `#`;
+///   nested `define`s are counted
+pub fn classify_line_makefile_like(line: &str, state: &mut MakefileState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    if state.define_depth > 0 {
+        match make_directive(trimmed) {
+            Some("define") => state.define_depth += 1,
+            Some("endef") => state.define_depth -= 1,
+            _ => {}
+        }
+        return (true, false);
+    }
+
+    let continues = trimmed.ends_with('\\');
+    if state.in_recipe && (line.starts_with('\t') || state.continued == Some(MakeLine::Recipe)) {
+        state.continued = continues.then_some(MakeLine::Recipe);
+        return classify_line_shell_like(line, &mut state.shell);
+    }
+
+    let bytes = line.as_bytes();
+    let comment_at =
+        (0..bytes.len()).find(|&i| bytes[i] == b'#' && (i == 0 || bytes[i - 1] != b'\\'));
+    let code = &line[..comment_at.unwrap_or(line.len())];
+    let saw_code = !code.trim().is_empty();
+
+    if state.continued == Some(MakeLine::Make) {
+        // Continuation of a variable or prerequisite list: keeps the current recipe state.
+    } else if saw_code && !is_make_conditional(code) {
+        state.in_recipe = is_make_rule(code);
+        state.shell = ShellState::new();
+        if make_directive(code.trim()) == Some("define") {
+            state.define_depth = 1;
+        }
+    }
+    state.continued = (continues && comment_at.is_none()).then_some(MakeLine::Make);
+
+    (saw_code, comment_at.is_some())
+}
+
+/// Whether a make line (comment removed) is a rule `targets: prerequisites`, as opposed to a
+/// variable assignment such as `A := b` or `A = b:c`.
+fn is_make_rule(code: &str) -> bool {
+    let Some(colon) = code.find(':') else {
+        return false;
+    };
+    if code[..colon].contains('=') || code[colon + 1..].starts_with('=') {
+        return false;
+    }
+    // `A ::= b` and `A :::= b` are assignments too.
+    !code[colon..].trim_start_matches(':').starts_with('=')
+}
+
+fn is_make_conditional(code: &str) -> bool {
+    let word = code.split_whitespace().next().unwrap_or("");
+    matches!(
+        word,
+        "ifeq" | "ifneq" | "ifdef" | "ifndef" | "else" | "endif"
+    )
+}
+
+/// The `define` / `endef` directive of a trimmed make line, if any; `define` may be
+/// preceded by `export` / `override` / `private`.
+fn make_directive(trimmed: &str) -> Option<&'static str> {
+    let mut words = trimmed
+        .split_whitespace()
+        .skip_while(|w| matches!(*w, "export" | "override" | "private"));
+    match words.next()? {
+        "define" => Some("define"),
+        "endef" if trimmed.starts_with("endef") => Some("endef"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MakeLine {
+    Make,
+    Recipe,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MakefileState {
+    /// After a rule line: tab-indented lines are its recipe.
+    pub in_recipe: bool,
+    /// The previous line ended with `\`, and which kind of line it was.
+    pub continued: Option<MakeLine>,
+    /// Shell state for the current recipe.
+    pub shell: ShellState,
+    /// Number of open `define` blocks.
+    pub define_depth: u32,
+}
 
-        i += 1;
+impl MakefileState {
+    pub fn new() -> Self {
+        Self::default()
     }
-    None
 }
 
-fn find_substring_outside_strings(haystack: &str, needle: &str) -> Option<usize> {
-    // best-effort: for our uses in this file, any quoted string should be skipped.
-    let bytes = haystack.as_bytes();
-    let n = needle.as_bytes();
+/// Nix:
+/// - line comments: `#`; block comments: `/* */`
+/// - strings: `"..."` (backslash escapes) and indented `''...''` strings (`'''`, `''$` and
+///   `''\` escapes), both of which may span lines
+pub fn classify_line_nix_like(line: &str, state: &mut NixState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
+    let bytes = line.as_bytes();
+    let mut saw_code = false;
+    let mut saw_comment = false;
     let mut i = 0usize;
-    let mut in_single = false;
-    let mut in_double = false;
 
-    while i + n.len() <= bytes.len() {
-        let b = bytes[i];
-        if in_single {
-            if b == b'\\' {
-                i += 2;
-                continue;
-            }
-            if b == b'\'' {
-                in_single = false;
+    while i < bytes.len() {
+        if state.in_block_comment {
+            saw_comment = true;
+            match find_closing(bytes, i, b"*/") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    i = end;
+                    continue;
+                }
+                None => break,
             }
-            i += 1;
-            continue;
         }
-        if in_double {
-            if b == b'\\' {
-                i += 2;
-                continue;
-            }
-            if b == b'"' {
-                in_double = false;
+
+        if let Some(s) = state.in_string {
+            saw_code = true;
+            let end = match s {
+                NixString::Double => scan_quoted(bytes, i, b'"'),
+                NixString::Indented => scan_nix_indented(bytes, i),
+            };
+            match end {
+                Some(end) => {
+                    state.in_string = None;
+                    i = end;
+                    continue;
+                }
+                None => break,
             }
-            i += 1;
-            continue;
         }
 
-        if b == b'\'' {
-            in_single = true;
-            i += 1;
+        let b = bytes[i];
+        if b == b'#' {
+            saw_comment = true;
+            break;
+        }
+        if match_at(bytes, i, b"/*").is_some() {
+            state.in_block_comment = true;
+            saw_comment = true;
+            i += 2;
             continue;
         }
         if b == b'"' {
-            in_double = true;
+            state.in_string = Some(NixString::Double);
+            saw_code = true;
             i += 1;
             continue;
         }
-
-        if &bytes[i..i + n.len()] == n {
-            return Some(i);
+        if match_at(bytes, i, b"''").is_some() {
+            state.in_string = Some(NixString::Indented);
+            saw_code = true;
+            i += 2;
+            continue;
         }
 
+        if !b.is_ascii_whitespace() {
+            saw_code = true;
+        }
         i += 1;
     }
 
+    (saw_code, saw_comment)
+}
+
+/// Scan an indented `''...''` string body from `i`; returns the index after the closing `''`.
+fn scan_nix_indented(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        if match_at(bytes, i, b"''").is_some() {
+            // `'''`, `''$` and `''\x` are escapes, not the end of the string.
+            match bytes.get(i + 2) {
+                Some(b'\'') | Some(b'$') => i += 3,
+                Some(b'\\') => i += 4,
+                _ => return Some(i + 2),
+            }
+            continue;
+        }
+        i += 1;
+    }
     None
 }
 
-fn classify_line_lua_line_comment(line: &str) -> (bool, bool) {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NixString {
+    /// `"..."`
+    Double,
+    /// `''...''`
+    Indented,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NixState {
+    pub in_block_comment: bool,
+    /// String continuing across lines.
+    pub in_string: Option<NixString>,
+}
+
+impl NixState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// JavaScript / TypeScript:
+/// - line comment: `//`, block comment: `/* */`
+/// - `'...'` / `"..."` strings, continued onto the next line by a trailing `\`
+/// - `` `...` `` template literals may span lines; `${ ... }` substitutions are code and
+///   may contain further templates
+/// - a `/` where an expression can start begins a regex literal (`/\/\//g`), while after a
+///   value (identifier, number, `)`, `]`) it is division
+pub fn classify_line_js_like(line: &str, state: &mut JsState) -> (bool, bool) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return (false, false);
+    }
+
     let bytes = line.as_bytes();
-    let mut i = 0usize;
-    let mut in_single = false;
-    let mut in_double = false;
     let mut saw_code = false;
     let mut saw_comment = false;
+    let mut i = 0usize;
 
     while i < bytes.len() {
-        let b = bytes[i];
-        if in_single {
-            if b == b'\\' {
-                i += 2;
-                continue;
-            }
-            if b == b'\'' {
-                in_single = false;
+        if state.in_block_comment {
+            saw_comment = true;
+            match find_closing(bytes, i, b"*/") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    i = end;
+                    continue;
+                }
+                None => break,
             }
-            saw_code = true;
-            i += 1;
-            continue;
         }
-        if in_double {
-            if b == b'\\' {
-                i += 2;
-                continue;
-            }
-            if b == b'"' {
-                in_double = false;
-            }
+
+        if state.in_template {
             saw_code = true;
-            i += 1;
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'`' => {
+                    state.in_template = false;
+                    state.after_value = true;
+                    i += 1;
+                }
+                b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                    state.template_braces.push(0);
+                    state.in_template = false;
+                    state.after_value = false;
+                    i += 2;
+                }
+                _ => i += 1,
+            }
             continue;
         }
 
-        if match_at(bytes, i, b"--").is_some() {
-            // everything after is comment
-            saw_comment = true;
-            break;
+        if let Some(q) = state.in_quote {
+            saw_code = true;
+            match scan_quoted(bytes, i, q) {
+                Some(end) => {
+                    state.in_quote = None;
+                    state.after_value = true;
+                    i = end;
+                    continue;
+                }
+                None => break,
+            }
         }
 
-        if b == b'\'' {
-            in_single = true;
-            saw_code = true;
-            i += 1;
+        let b = bytes[i];
+        if match_at(bytes, i, b"/*").is_some() {
+            state.in_block_comment = true;
+            saw_comment = true;
+            i += 2;
             continue;
         }
-        if b == b'"' {
-            in_double = true;
-            saw_code = true;
+        if match_at(bytes, i, b"//").is_some() {
+            saw_comment = true;
+            break;
+        }
+        if b.is_ascii_whitespace() {
             i += 1;
             continue;
         }
 
-        if !b.is_ascii_whitespace() {
-            saw_code = true;
+        saw_code = true;
+        match b {
+            b'\'' | b'"' => {
+                state.in_quote = Some(b);
+                i += 1;
+            }
+            b'`' => {
+                state.in_template = true;
+                i += 1;
+            }
+            b'{' => {
+                if let Some(depth) = state.template_braces.last_mut() {
+                    *depth += 1;
+                }
+                state.after_value = false;
+                i += 1;
+            }
+            b'}' => {
+                match state.template_braces.last_mut() {
+                    Some(0) => {
+                        state.template_braces.pop();
+                        state.in_template = true;
+                    }
+                    Some(depth) => *depth -= 1,
+                    None => {}
+                }
+                state.after_value = true;
+                i += 1;
+            }
+            b'/' if !state.after_value => match scan_js_regex(bytes, i + 1) {
+                Some(end) => {
+                    state.after_value = true;
+                    i = end;
+                }
+                // No closing `/` on this line, so it was an operator after all.
+                None => i += 1,
+            },
+            b')' | b']' => {
+                state.after_value = true;
+                i += 1;
+            }
+            _ if is_js_ident_byte(b) => {
+                let len = bytes[i..]
+                    .iter()
+                    .take_while(|&&c| is_js_ident_byte(c))
+                    .count();
+                state.after_value = !is_js_expr_keyword(&bytes[i..i + len]);
+                i += len;
+            }
+            _ => {
+                state.after_value = false;
+                i += 1;
+            }
         }
-        i += 1;
+    }
+
+    // Plain strings only continue onto the next line after a trailing `\`.
+    if state.in_quote.is_some() && !line.trim_end().ends_with('\\') {
+        state.in_quote = None;
+        state.after_value = true;
     }
 
     (saw_code, saw_comment)
 }
 
-/// Batch/CMD-like:
-/// - comment line: leading `REM` (case-insensitive) or leading `::`
-///
-/// This is a best-effort heuristic used for cloc-style counting.
-pub fn classify_line_batch_like(line: &str) -> (bool, bool) {
-    let trimmed = line.trim();
-    if trimmed.is_empty() {
-        return (false, false);
-    }
+fn is_js_ident_byte(b: u8) -> bool {
+    is_ident_byte(b) || b == b'$' || b >= 0x80
+}
 
-    // `::` comment (common batch trick). Only treat it as comment when it appears at start.
-    if trimmed.starts_with("::") {
-        return (false, true);
-    }
+/// Keywords after which an expression follows, so a `/` starts a regex.
+fn is_js_expr_keyword(word: &[u8]) -> bool {
+    matches!(
+        word,
+        b"return"
+            | b"typeof"
+            | b"instanceof"
+            | b"in"
+            | b"of"
+            | b"new"
+            | b"delete"
+            | b"void"
+            | b"throw"
+            | b"case"
+            | b"do"
+            | b"else"
+            | b"yield"
+            | b"await"
+    )
+}
 
-    // `REM` comment. Only treat it as comment when it appears at start and is a whole word.
-    // Accept: REM, REM<space>, REM\t...
-    // Reject: REMARK, REM1, echo REM ... (since it's not at line start after trim).
-    let t = trimmed;
-    if t.len() >= 3 {
-        let prefix = &t[..3];
-        if prefix.eq_ignore_ascii_case("rem") {
-            let rest = &t[3..];
-            if rest.is_empty() {
-                return (false, true);
-            }
-            if rest
-                .chars()
-                .next()
-                .is_some_and(|c| c.is_ascii_whitespace())
-            {
-                return (false, true);
+/// Scan a regex literal body from `i` (just after the opening `/`); returns the index after
+/// the closing `/` and any flags, or `None` if the line ends first.
+fn scan_js_regex(bytes: &[u8], mut i: usize) -> Option<usize> {
+    let mut in_class = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' => in_class = true,
+            b']' => in_class = false,
+            b'/' if !in_class => {
+                let flags = bytes[i + 1..]
+                    .iter()
+                    .take_while(|&&b| is_ident_byte(b))
+                    .count();
+                return Some(i + 1 + flags);
             }
+            _ => {}
         }
+        i += 1;
     }
+    None
+}
 
-    (true, false)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsState {
+    pub in_block_comment: bool,
+    /// Inside the text of a template literal.
+    pub in_template: bool,
+    /// One entry per open `${` substitution: the number of unclosed `{` inside it.
+    pub template_braces: Vec<u32>,
+    /// Quote of a string continued onto the next line by a trailing `\`.
+    pub in_quote: Option<u8>,
+    /// The previous token ends a value, so a `/` here is division rather than a regex.
+    pub after_value: bool,
 }
 
-/// SQL-like:
-/// - line comment: `--`
-/// - block comment: `/* */`
-/// - string literals: single and double quotes
-pub fn classify_line_sql_like(line: &str, state: &mut ParseState) -> (bool, bool) {
-    classify_line_sql_generic(line, state)
+impl JsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
 }
 
-fn classify_line_sql_generic(line: &str, state: &mut ParseState) -> (bool, bool) {
+/// C / C++:
+/// - line comment: `//`, continued onto the next line by a trailing `\`
+/// - block comment: `/* */`
+/// - `"..."` strings and `'...'` character literals; `'` inside a number is a digit
+///   separator (`1'000'000`)
+/// - raw strings (`R"(...)"`, `u8R"delim(...)delim"`) may span lines and their contents are code
+pub fn classify_line_cpp_like(line: &str, state: &mut ParseState) -> (bool, bool) {
     let trimmed = line.trim();
     if trimmed.is_empty() {
+        state.continued_comment = false;
         return (false, false);
     }
+    if state.continued_comment {
+        state.continued_comment = trimmed.ends_with('\\');
+        return (false, true);
+    }
 
+    let bytes = line.as_bytes();
     let mut saw_code = false;
     let mut saw_comment = false;
-
-    let bytes = line.as_bytes();
     let mut i = 0usize;
 
-    let mut in_string_single = false;
-    let mut in_string_double = false;
-
     while i < bytes.len() {
         if state.in_block_comment {
-            if match_at(bytes, i, b"*/").is_some() {
-                state.in_block_comment = false;
-                saw_comment = true;
-                i += 2;
-                continue;
-            }
             saw_comment = true;
-            i += 1;
-            continue;
-        }
-
-        // strings (best-effort)
-        let b = bytes[i];
-        if in_string_single {
-            if b == b'\\' {
-                i += 2;
-                continue;
-            }
-            if b == b'\'' {
-                in_string_single = false;
-            }
-            saw_code = true;
-            i += 1;
-            continue;
-        }
-
-        if in_string_double {
-            if b == b'\\' {
-                i += 2;
-                continue;
-            }
-            if b == b'"' {
-                in_string_double = false;
+            match find_closing(bytes, i, b"*/") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    i = end;
+                    continue;
+                }
+                None => break,
             }
-            saw_code = true;
-            i += 1;
-            continue;
         }
 
-        if b == b'\'' {
-            in_string_single = true;
-            saw_code = true;
-            i += 1;
-            continue;
-        }
-        if b == b'"' {
-            in_string_double = true;
+        if let Some(end) = &state.raw_string_end {
             saw_code = true;
-            i += 1;
-            continue;
+            match find_closing(bytes, i, end.as_bytes()) {
+                Some(after) => {
+                    state.raw_string_end = None;
+                    i = after;
+                    continue;
+                }
+                None => break,
+            }
         }
 
-        // block comment start
+        let b = bytes[i];
         if match_at(bytes, i, b"/*").is_some() {
             state.in_block_comment = true;
             saw_comment = true;
             i += 2;
             continue;
         }
-
-        // line comment start
-        if match_at(bytes, i, b"--").is_some() {
+        if match_at(bytes, i, b"//").is_some() {
             saw_comment = true;
+            state.continued_comment = trimmed.ends_with('\\');
             break;
         }
-
         if !b.is_ascii_whitespace() {
             saw_code = true;
         }
-        i += 1;
+
+        match b {
+            b'"' => {
+                if let Some((open, end)) = cpp_raw_string_start(bytes, i) {
+                    state.raw_string_end = Some(end);
+                    i = open;
+                    continue;
+                }
+                i = scan_quoted(bytes, i + 1, b'"').unwrap_or(bytes.len());
+            }
+            b'\'' if !is_digit_separator(bytes, i) => {
+                i = scan_quoted(bytes, i + 1, b'\'').unwrap_or(bytes.len());
+            }
+            _ => i += 1,
+        }
     }
 
     (saw_code, saw_comment)
 }
+
+/// If the `"` at `i` opens a raw string (`R"`, `LR"`, `uR"`, `UR"`, `u8R"`), returns the index
+/// after its `(` and the closing sequence `)delim"`.
+fn cpp_raw_string_start(bytes: &[u8], i: usize) -> Option<(usize, String)> {
+    let prefix_start = bytes[..i]
+        .iter()
+        .rposition(|&b| !is_ident_byte(b))
+        .map_or(0, |p| p + 1);
+    let prefix = &bytes[prefix_start..i];
+    if !matches!(prefix, b"R" | b"LR" | b"uR" | b"UR" | b"u8R") {
+        return None;
+    }
+    // The delimiter is at most 16 characters and cannot contain spaces, parentheses or `\`.
+    let delim_len = bytes[i + 1..].iter().take(17).position(|&b| b == b'(')?;
+    let delim = &bytes[i + 1..i + 1 + delim_len];
+    if delim
+        .iter()
+        .any(|&b| b.is_ascii_whitespace() || matches!(b, b')' | b'\\' | b'"'))
+    {
+        return None;
+    }
+    let end = format!("){}\"", String::from_utf8_lossy(delim));
+    Some((i + 2 + delim_len, end))
+}
+
+/// Whether the `'` at `i` separates digits of a number literal (`1'000`, `0xFF'FF`).
+fn is_digit_separator(bytes: &[u8], i: usize) -> bool {
+    if i == 0 || !bytes[i - 1].is_ascii_alphanumeric() {
+        return false;
+    }
+    if !bytes.get(i + 1).is_some_and(u8::is_ascii_alphanumeric) {
+        return false;
+    }
+    let token_start = bytes[..i]
+        .iter()
+        .rposition(|&b| !(is_ident_byte(b) || b == b'\'' || b == b'.'))
+        .map_or(0, |p| p + 1);
+    bytes[token_start].is_ascii_digit()
+}
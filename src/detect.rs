@@ -0,0 +1,118 @@
+//! Content heuristics for files that are not hand-written source.
+//!
+//! These run on top of the line classifiers: the parsing loop feeds each header
+//! line together with its classification, so banners are only trusted when
+//! they actually sit inside a comment.
+
+use std::collections::HashMap;
+
+use crate::model::{CodeFileData, Digest};
+
+/// How many leading lines are inspected for generator banners.
+pub const GENERATED_SCAN_LINES: usize = 20;
+
+/// Banners written by code generators; matched case-insensitively inside comments.
+const GENERATED_COMMENT_MARKERS: &[&str] = &[
+    // Facebook / Meta convention, also used by many JS tools.
+    "@generated",
+    // Go's `Code generated by X.` banner ends with this do-not-edit notice; most
+    // other generators use it too.
+    "do not edit",
+    "auto-generated",
+    "autogenerated",
+    "automatically generated",
+    // protoc banners
+    "generated by the protocol buffer compiler",
+    "generated by protoc",
+];
+
+/// Markers that identify generated lock files, which are plain data with no comments.
+/// Only checked in files accepted by `is_lock_file`.
+const GENERATED_DATA_MARKERS: &[&str] = &[
+    // npm package-lock.json / npm-shrinkwrap.json
+    "\"lockfileversion\"",
+    // pnpm-lock.yaml
+    "lockfileversion:",
+];
+
+/// Whether `path` names a JSON or YAML lock file, e.g. `package-lock.json` or `pnpm-lock.yaml`.
+pub fn is_lock_file(path: &str) -> bool {
+    let file_name = path.rsplit(['/', '\\', '!']).next().unwrap_or(path);
+    let file_name = file_name.to_ascii_lowercase();
+    let Some((stem, ext)) = file_name.rsplit_once('.') else {
+        return false;
+    };
+    matches!(ext, "json" | "yaml" | "yml") && (stem.contains("lock") || stem.contains("shrinkwrap"))
+}
+
+/// Whether a header `line` marks the file as generated.
+///
+/// `in_comment` is whether the classifier saw a comment on this line; `lock_file` is
+/// whether the file is a lock file (see `is_lock_file`), where data markers also count.
+pub fn is_generated_marker(line: &str, in_comment: bool, lock_file: bool) -> bool {
+    let lc = line.to_ascii_lowercase();
+    if in_comment && GENERATED_COMMENT_MARKERS.iter().any(|m| lc.contains(m)) {
+        return true;
+    }
+    lock_file && GENERATED_DATA_MARKERS.iter().any(|m| lc.contains(m))
+}
+
+/// Group byte-identical files by content digest.
+///
+/// Returns groups of two or more indices into `files`, each sorted by path so the file
+/// that is kept comes first. Empty files and files without a digest are not reported.
+pub fn find_duplicates(files: &[CodeFileData]) -> Vec<Vec<usize>> {
+    let mut by_content: HashMap<(&Digest, u64), Vec<usize>> = HashMap::new();
+    for (i, f) in files.iter().enumerate() {
+        let Some(digest) = f.digest() else {
+            continue;
+        };
+        if f.bytes() == 0 {
+            continue;
+        }
+        by_content.entry((digest, f.bytes())).or_default().push(i);
+    }
+
+    let mut groups: Vec<Vec<usize>> = by_content
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .map(|mut g| {
+            g.sort_by_key(|&i| (files[i].path(), i));
+            g
+        })
+        .collect();
+    groups.sort_by_key(|g| files[g[0]].path());
+    groups
+}
+
+/// Average non-blank line length, in characters, above which an asset is treated as minified.
+pub const MINIFIED_AVG_LINE_LEN: usize = 300;
+
+/// Whether a file looks minified or bundled rather than hand-written.
+///
+/// Signals: a `.min.` file name, a source map reference, or (for `asset` files only, i.e.
+/// JavaScript and CSS, which get bundled) a very long average line length. Other files may
+/// legitimately have long lines, such as notebook outputs or CJK prose.
+pub fn is_minified(path: &str, content: &str, asset: bool) -> bool {
+    let file_name = path.rsplit(['/', '\\', '!']).next().unwrap_or(path);
+    if file_name.to_ascii_lowercase().contains(".min.") {
+        return true;
+    }
+
+    let mut lines = 0usize;
+    let mut chars = 0usize;
+    for line in content.lines() {
+        let t = line.trim();
+        if t.is_empty() {
+            continue;
+        }
+        // `//# sourceMappingURL=...` (JS) or `/*# sourceMappingURL=... */` (CSS)
+        if (t.starts_with("//#") || t.starts_with("/*#")) && t.contains("sourceMappingURL=") {
+            return true;
+        }
+        lines += 1;
+        chars += t.chars().count();
+    }
+
+    asset && lines > 0 && chars / lines > MINIFIED_AVG_LINE_LEN
+}
@@ -1,3 +1,5 @@
+pub mod archive;
 pub mod comment_parser;
+pub mod detect;
 pub mod model;
-
+pub mod notebook;
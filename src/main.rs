@@ -1,26 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::{env, io};
 
 use chardet::detect;
 use encoding::DecoderTrap;
 use encoding::label::encoding_from_whatwg_label;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use rayon::prelude::*;
+use sha2::{Digest as _, Sha256};
 use std::fs::File;
 use std::io::{BufReader, ErrorKind, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 use walkdir::WalkDir;
 
+mod archive;
+mod detect;
 mod model;
+mod notebook;
 
-use model::{CliOptions, CodeFileData, ParserKind};
+use archive::{is_archive_path, read_archive};
+use detect::{
+    GENERATED_SCAN_LINES, find_duplicates, is_generated_marker, is_lock_file, is_minified,
+};
+use model::{Candidate, CliOptions, CodeFileData, ParserKind, ReportMode, SqlDialect};
+use notebook::{CellKind, parse_notebook};
 
 mod comment_parser;
 use crate::comment_parser::{
-    LuaState, ParseState, PythonState,
-    classify_line_batch_like, classify_line_c_like, classify_line_css_like,
-    classify_line_lua_like, classify_line_python_like, classify_line_xml_like,
-    classify_line_sql_like,
+    AsmDialect, AsmState, Classifier, HtmlState, MarkdownState, ParseState, SqlState,
+    classify_line_html_like, classify_line_markdown_like,
 };
 
 const APP_NAME: &str = "cloc";
@@ -33,10 +41,11 @@ fn show_version() {
 fn show_help() {
     println!(
         r#"用法:
-  cloc [options] [path]
+  cloc [options] [path...]
 
 Arguments:
-  path                扫描目录 (默认当前目录)
+  path                扫描目录或文件，可传多个并合并统计 (默认当前目录)
+                      .tar/.tar.gz/.tgz/.zip 压缩包会在内存中直接统计
 
 Options:
   -h, --help          显示帮助信息
@@ -45,10 +54,31 @@ Options:
   --max-bytes <N>     跳过大文件，默认16M(16777216字节)
   --no-binary-skip    不跳过疑似二进制文件
   --exclude-dir <N>   排除目录， 默认排除目录(.git, target, node_modules)
+  --exclude-glob <P>  按相对路径的 glob 排除（可重复），如 src/generated/**
+  --exclude-list-file <F>
+                      从文件读取 glob 排除规则（每行一条，# 开头为注释）
+  --list-file <F>     从文件读取待扫描路径（每行一个），- 表示从 stdin 读取
+  --extract <F>       将文件作为压缩包读取（不依赖扩展名）
+  --by-file           按文件输出统计
+  --count-duplicates  重复文件（内容完全相同）也分别计数，默认只计一次
+  --report-duplicates 列出重复文件分组
+  --generated <M>     生成文件（@generated / DO NOT EDIT 等标记）的处理方式:
+                      separate(默认，单独归入 generated 行) / exclude / include
+  --minified <M>      压缩/打包文件（.min.、sourceMappingURL、超长行）的处理方式:
+                      separate(默认，单独归入 minified 行) / exclude / include
+  --minified-bytes    额外输出压缩/打包文件的字节数
+  --split-embedded    内嵌代码（如 HTML/Vue/Svelte 中的 <script>/<style>）
+                      计入各自语言，默认计入宿主语言
+  --sql-dialect <D>   .sql 文件的 SQL 方言: ansi(默认) / mysql / postgres / tsql
+                      （.mysql / .pgsql / .tsql 始终按各自方言）
 
 示例:
   cloc .
+  cloc src tests build.rs
+  git ls-files | cloc --list-file -
+  cloc --by-file release.tar.gz
   cloc --exclude-dir target --exclude-dir .git .
+  cloc --exclude-glob "src/generated/**" --exclude-list-file .clocignore .
   cloc --no-parallel D:\\repo
   cloc --max-bytes 1048576 .
 "#
@@ -69,6 +99,79 @@ fn show_header() {
     println!("-------------------------------------------------------------------------------");
 }
 
+fn show_duplicates(groups: &[Vec<String>]) {
+    println!("Duplicate files ({} groups):", groups.len());
+    for g in groups {
+        println!("  {}", g[0]);
+        for dup in &g[1..] {
+            println!("    = {dup}");
+        }
+    }
+    println!();
+}
+
+/// Per-language bytes and lines of minified files: line counts alone hide their size.
+fn show_minified_bytes(files: &[(String, u64, u64)]) {
+    let mut map: HashMap<&str, (u64, u64, u64)> = HashMap::new();
+    for (lang, bytes, lines) in files {
+        let v = map.entry(lang.as_str()).or_insert((0, 0, 0));
+        v.0 += 1;
+        v.1 += bytes;
+        v.2 += lines;
+    }
+
+    let mut rows: Vec<_> = map.into_iter().collect();
+    rows.sort_by_key(|(lang, _)| *lang);
+
+    println!(
+        "{:<W$} {:>W$} {:>W$} {:>W$}",
+        "Minified",
+        "files",
+        "bytes",
+        "lines",
+        W = 15
+    );
+    show_dash_line();
+    for (lang, value) in rows {
+        println!(
+            "{:<W$} {:>W$} {:>W$} {:>W$}",
+            lang,
+            value.0,
+            value.1,
+            value.2,
+            W = 15
+        );
+    }
+    show_dash_line();
+}
+
+fn show_by_file(files: &[CodeFileData]) {
+    let mut rows: Vec<&CodeFileData> = files.iter().collect();
+    rows.sort_by_key(|f| f.path());
+
+    show_dash_line();
+    println!(
+        "{:<45} {:>10} {:>10} {:>10}",
+        "File", "blank", "comment", "code"
+    );
+    show_dash_line();
+    for f in rows {
+        let (mut blank, mut comment, mut code) = (f.blank(), f.comment(), f.code());
+        for (_, b, m, c) in f.embedded() {
+            blank += b;
+            comment += m;
+            code += c;
+        }
+        println!(
+            "{:<45} {:>10} {:>10} {:>10}",
+            f.path(),
+            blank,
+            comment,
+            code
+        );
+    }
+}
+
 fn show_dash_line() {
     println!("-------------------------------------------------------------------------------");
 }
@@ -79,68 +182,277 @@ fn show_dash_line() {
 ///
 /// To add a new file type, add one entry here.
 const PATTERNS: &[(&str, ParserKind)] = &[
+    // C / C++ (raw strings, digit separators)
+    ("c", ParserKind::Cpp),
+    ("cc", ParserKind::Cpp),
+    ("cpp", ParserKind::Cpp),
+    ("h", ParserKind::Cpp),
     // C-like
-    ("c", ParserKind::CLike),
-    ("cc", ParserKind::CLike),
-    ("cpp", ParserKind::CLike),
-    ("h", ParserKind::CLike),
     ("rs", ParserKind::CLike),
     ("java", ParserKind::CLike),
-    ("go", ParserKind::CLike),
-    ("swift", ParserKind::CLike),
-    ("cs", ParserKind::CLike),
     ("m", ParserKind::CLike),
     ("mm", ParserKind::CLike),
-    ("kt", ParserKind::CLike),
-    ("js", ParserKind::CLike),
-    ("ts", ParserKind::CLike),
-    ("jsx", ParserKind::CLike),
-    ("tsx", ParserKind::CLike),
-    ("dart", ParserKind::CLike),
+    // Go / C# (multi-line raw and verbatim strings)
+    ("go", ParserKind::Go),
+    ("cs", ParserKind::CSharp),
+    // JavaScript / TypeScript (template literals, regex literals)
+    ("js", ParserKind::JavaScript),
+    ("ts", ParserKind::JavaScript),
+    ("jsx", ParserKind::JavaScript),
+    ("tsx", ParserKind::JavaScript),
+    // C-like with nesting /* */
+    ("swift", ParserKind::CNested),
+    ("kt", ParserKind::CNested),
+    ("scala", ParserKind::CNested),
+    ("dart", ParserKind::CNested),
     // Build / config that are mostly C-like
     ("gradle", ParserKind::CLike),
     // NOTE: json is treated as jsonc (supports // and /* */) for practicality.
     ("json", ParserKind::CLike),
-
     // Hash-comment based (reuse Python-like '#', with basic string awareness)
     ("py", ParserKind::Python),
-    ("sh", ParserKind::Python),
-    ("bash", ParserKind::Python),
-    ("toml", ParserKind::Python),
-    ("yml", ParserKind::Python),
-    ("yaml", ParserKind::Python),
-
+    // Config files
+    ("toml", ParserKind::Toml),
+    ("yml", ParserKind::Yaml),
+    ("yaml", ParserKind::Yaml),
+    // Shell family
+    ("sh", ParserKind::Shell),
+    ("bash", ParserKind::Shell),
+    ("zsh", ParserKind::Shell),
+    ("ksh", ParserKind::Shell),
+    ("fish", ParserKind::Shell),
     // Lua
     ("lua", ParserKind::Lua),
-
-    // Markup / docs with <!-- -->
-    ("html", ParserKind::Xml),
-    ("htm", ParserKind::Xml),
+    // Ruby / Perl
+    ("rb", ParserKind::Ruby),
+    ("rake", ParserKind::Ruby),
+    ("gemspec", ParserKind::Ruby),
+    ("pl", ParserKind::Perl),
+    ("pm", ParserKind::Perl),
+    ("t", ParserKind::Perl),
+    // Functional languages with nesting block comments
+    ("hs", ParserKind::Haskell),
+    ("elm", ParserKind::Haskell),
+    ("ml", ParserKind::OCaml),
+    ("mli", ParserKind::OCaml),
+    ("fs", ParserKind::FSharp),
+    ("fsi", ParserKind::FSharp),
+    ("fsx", ParserKind::FSharp),
+    // Lisp family
+    ("clj", ParserKind::Clojure),
+    ("cljs", ParserKind::Clojure),
+    ("cljc", ParserKind::Clojure),
+    ("edn", ParserKind::Clojure),
+    ("scm", ParserKind::Scheme),
+    ("ss", ParserKind::Scheme),
+    ("sld", ParserKind::Scheme),
+    ("rkt", ParserKind::Scheme),
+    ("lisp", ParserKind::Lisp),
+    ("lsp", ParserKind::Lisp),
+    ("el", ParserKind::Lisp),
+    // Column-sensitive legacy languages
+    ("f", ParserKind::FortranFixed),
+    ("for", ParserKind::FortranFixed),
+    ("ftn", ParserKind::FortranFixed),
+    ("f77", ParserKind::FortranFixed),
+    ("f90", ParserKind::FortranFree),
+    ("f95", ParserKind::FortranFree),
+    ("f03", ParserKind::FortranFree),
+    ("f08", ParserKind::FortranFree),
+    ("cob", ParserKind::Cobol),
+    ("cbl", ParserKind::Cobol),
+    ("cpy", ParserKind::Cobol),
+    // Assembly (`.S` / `.sx` also go through the C preprocessor)
+    ("s", ParserKind::Asm),
+    ("sx", ParserKind::Asm),
+    ("asm", ParserKind::Asm),
+    ("nasm", ParserKind::Asm),
+    // Infrastructure as code (`Dockerfile*` and `Makefile` are matched via `FILE_NAMES`)
+    ("tf", ParserKind::Hcl),
+    ("tfvars", ParserKind::Hcl),
+    ("hcl", ParserKind::Hcl),
+    ("dockerfile", ParserKind::Dockerfile),
+    ("makefile", ParserKind::Makefile),
+    ("mk", ParserKind::Makefile),
+    ("mak", ParserKind::Makefile),
+    ("nix", ParserKind::Nix),
+    // PHP (with inline HTML)
+    ("php", ParserKind::Php),
+    ("phtml", ParserKind::Php),
+    // Markup with embedded <script> / <style>
+    ("html", ParserKind::Html),
+    ("htm", ParserKind::Html),
+    ("vue", ParserKind::Html),
+    ("svelte", ParserKind::Html),
+    // Markup with <!-- -->
     ("xml", ParserKind::Xml),
     ("wxml", ParserKind::Xml),
-    ("md", ParserKind::Xml),
-
+    // Docs: prose plus fenced code blocks
+    ("md", ParserKind::Markdown),
+    ("markdown", ParserKind::Markdown),
+    // Jupyter notebooks (cells extracted from the JSON)
+    ("ipynb", ParserKind::Notebook),
     // Styles
     ("css", ParserKind::Css),
     ("scss", ParserKind::Css),
     ("less", ParserKind::Css),
     ("wxss", ParserKind::Css),
-
     // Windows scripts
     ("bat", ParserKind::Batch),
     ("cmd", ParserKind::Batch),
-
+    ("ps1", ParserKind::PowerShell),
+    ("psm1", ParserKind::PowerShell),
+    ("psd1", ParserKind::PowerShell),
+    ("vbs", ParserKind::VisualBasic),
+    ("vb", ParserKind::VisualBasic),
     // Kotlin script
-    ("kts", ParserKind::CLike),
-
+    ("kts", ParserKind::CNested),
     // Plain text (no comments)
     ("txt", ParserKind::PlainText),
     ("log", ParserKind::PlainText),
-
-    // SQL
+    // SQL (dialect-specific extensions override --sql-dialect)
     ("sql", ParserKind::Sql),
+    ("mysql", ParserKind::Sql),
+    ("pgsql", ParserKind::Sql),
+    ("tsql", ParserKind::Sql),
+];
+
+/// Well-known file names without a usable extension, mapped to a `PATTERNS` key.
+/// Names match case-insensitively; a trailing `*` matches any suffix, e.g. `Dockerfile.dev`.
+const FILE_NAMES: &[(&str, &str)] = &[
+    ("Dockerfile*", "dockerfile"),
+    ("Containerfile*", "dockerfile"),
+    ("Makefile", "makefile"),
+    ("GNUmakefile", "makefile"),
+];
+
+/// Report names for extensions that are not reported as the bare extension,
+/// e.g. because several extensions belong to the same language.
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("mysql", "sql"),
+    ("pgsql", "sql"),
+    ("tsql", "sql"),
+    ("toml", "TOML"),
+    ("yml", "YAML"),
+    ("yaml", "YAML"),
+    ("md", "Markdown"),
+    ("markdown", "Markdown"),
+    ("ipynb", "Jupyter"),
+    ("php", "PHP"),
+    ("phtml", "PHP"),
+    ("rb", "Ruby"),
+    ("rake", "Ruby"),
+    ("gemspec", "Ruby"),
+    ("pl", "Perl"),
+    ("pm", "Perl"),
+    ("t", "Perl"),
+    ("hs", "Haskell"),
+    ("elm", "Elm"),
+    ("ml", "OCaml"),
+    ("mli", "OCaml"),
+    ("fs", "F#"),
+    ("fsi", "F#"),
+    ("fsx", "F#"),
+    ("clj", "Clojure"),
+    ("cljs", "ClojureScript"),
+    ("cljc", "Clojure"),
+    ("edn", "EDN"),
+    ("scm", "Scheme"),
+    ("ss", "Scheme"),
+    ("sld", "Scheme"),
+    ("rkt", "Racket"),
+    ("lisp", "Lisp"),
+    ("lsp", "Lisp"),
+    ("el", "Emacs Lisp"),
+    ("f", "Fortran 77"),
+    ("for", "Fortran 77"),
+    ("ftn", "Fortran 77"),
+    ("f77", "Fortran 77"),
+    ("f90", "Fortran 90"),
+    ("f95", "Fortran 90"),
+    ("f03", "Fortran 90"),
+    ("f08", "Fortran 90"),
+    ("cob", "COBOL"),
+    ("cbl", "COBOL"),
+    ("cpy", "COBOL"),
+    ("s", "Assembly"),
+    ("sx", "Assembly"),
+    ("asm", "Assembly"),
+    ("nasm", "Assembly"),
+    ("ps1", "PowerShell"),
+    ("psm1", "PowerShell"),
+    ("psd1", "PowerShell"),
+    ("vbs", "VBScript"),
+    ("vb", "Visual Basic"),
+    ("tf", "HCL"),
+    ("tfvars", "HCL"),
+    ("hcl", "HCL"),
+    ("dockerfile", "Dockerfile"),
+    ("makefile", "Makefile"),
+    ("mk", "Makefile"),
+    ("mak", "Makefile"),
+    ("nix", "Nix"),
 ];
 
+/// Language names (Markdown fence tags, notebook kernel languages) that are not
+/// themselves a known extension.
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("rust", "rs"),
+    ("python", "py"),
+    ("python3", "py"),
+    ("javascript", "js"),
+    ("typescript", "ts"),
+    ("shell", "sh"),
+    ("console", "sh"),
+    ("c++", "cpp"),
+    ("csharp", "cs"),
+    ("c#", "cs"),
+    ("kotlin", "kt"),
+    ("golang", "go"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("haskell", "hs"),
+    ("ocaml", "ml"),
+    ("fsharp", "fs"),
+    ("f#", "fs"),
+    ("clojure", "clj"),
+    ("scheme", "scm"),
+    ("racket", "rkt"),
+    ("elisp", "el"),
+    ("emacs-lisp", "el"),
+    ("common-lisp", "lisp"),
+    ("fortran", "f90"),
+    ("cobol", "cob"),
+    ("assembly", "s"),
+    ("gas", "s"),
+    ("arm", "s"),
+    ("powershell", "ps1"),
+    ("pwsh", "ps1"),
+    ("vbscript", "vbs"),
+    ("vbnet", "vb"),
+    ("terraform", "tf"),
+    ("docker", "dockerfile"),
+    ("make", "makefile"),
+    ("objc", "m"),
+    ("objective-c", "m"),
+];
+
+/// Extension (as listed in `PATTERNS`) for a language name such as `rust` or `py`.
+fn ext_for_language_name(tag: &str) -> Option<&'static str> {
+    let tag = LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == tag)
+        .map_or(tag, |(_, ext)| *ext);
+    PATTERNS.iter().find(|(e, _)| *e == tag).map(|(e, _)| *e)
+}
+
+fn language_for_ext(ext: &str) -> &str {
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map_or(ext, |(_, name)| *name)
+}
+
 fn parser_for_ext(ext: &str) -> Option<ParserKind> {
     // Linear scan is fine here; extensions list is tiny.
     PATTERNS.iter().find(|(e, _)| *e == ext).map(|(_, k)| *k)
@@ -166,6 +478,46 @@ fn parse_args() -> Result<CliOptions, String> {
             "--no-binary-skip" => {
                 opts.binary_skip = false;
             }
+            "--by-file" => {
+                opts.by_file = true;
+            }
+            "--count-duplicates" => {
+                opts.count_duplicates = true;
+            }
+            "--report-duplicates" => {
+                opts.report_duplicates = true;
+            }
+            "--generated" => {
+                let Some(v) = args.next() else {
+                    return Err("--generated requires a value".to_string());
+                };
+                opts.generated = parse_report_mode("--generated", &v)?;
+            }
+            "--minified" => {
+                let Some(v) = args.next() else {
+                    return Err("--minified requires a value".to_string());
+                };
+                opts.minified = parse_report_mode("--minified", &v)?;
+            }
+            "--minified-bytes" => {
+                opts.minified_bytes = true;
+            }
+            "--split-embedded" => {
+                opts.split_embedded = true;
+            }
+            "--sql-dialect" => {
+                let Some(v) = args.next() else {
+                    return Err("--sql-dialect requires a value".to_string());
+                };
+                opts.sql_dialect = parse_sql_dialect("--sql-dialect", &v)?;
+            }
+            "--extract" => {
+                let Some(v) = args.next() else {
+                    return Err("--extract requires a value".to_string());
+                };
+                opts.extract.push(v.clone());
+                opts.paths.push(v);
+            }
             "--exclude-dir" => {
                 let Some(v) = args.next() else {
                     return Err("--exclude-dir requires a value".to_string());
@@ -173,6 +525,24 @@ fn parse_args() -> Result<CliOptions, String> {
                 // allow user to add more excludes on top of defaults
                 opts.exclude_dirs.push(v);
             }
+            "--exclude-glob" => {
+                let Some(v) = args.next() else {
+                    return Err("--exclude-glob requires a value".to_string());
+                };
+                opts.exclude_globs.push(v);
+            }
+            "--exclude-list-file" => {
+                let Some(v) = args.next() else {
+                    return Err("--exclude-list-file requires a value".to_string());
+                };
+                opts.exclude_globs.extend(read_exclude_list_file(&v)?);
+            }
+            "--list-file" => {
+                let Some(v) = args.next() else {
+                    return Err("--list-file requires a value".to_string());
+                };
+                opts.paths.extend(read_list_file(&v)?);
+            }
             "--max-bytes" => {
                 let Some(v) = args.next() else {
                     return Err("--max-bytes requires a value".to_string());
@@ -185,8 +555,8 @@ fn parse_args() -> Result<CliOptions, String> {
                 if arg.starts_with('-') {
                     return Err(format!("unknown option: {arg}"));
                 }
-                // positional path (first one wins)
-                opts.path = arg;
+                // positional paths accumulate; all are merged into one report
+                opts.paths.push(arg);
             }
         }
     }
@@ -194,6 +564,131 @@ fn parse_args() -> Result<CliOptions, String> {
     Ok(opts)
 }
 
+fn parse_report_mode(flag: &str, v: &str) -> Result<ReportMode, String> {
+    match v {
+        "include" => Ok(ReportMode::Include),
+        "separate" => Ok(ReportMode::Separate),
+        "exclude" => Ok(ReportMode::Exclude),
+        _ => Err(format!(
+            "invalid {flag} value: {v} (expected include, separate or exclude)"
+        )),
+    }
+}
+
+fn parse_sql_dialect(flag: &str, v: &str) -> Result<SqlDialect, String> {
+    match v {
+        "ansi" => Ok(SqlDialect::Ansi),
+        "mysql" => Ok(SqlDialect::MySql),
+        "postgres" => Ok(SqlDialect::Postgres),
+        "tsql" => Ok(SqlDialect::TSql),
+        _ => Err(format!(
+            "invalid {flag} value: {v} (expected ansi, mysql, postgres or tsql)"
+        )),
+    }
+}
+
+/// Read paths to scan from a list file (or stdin for `-`): one path per line, blank lines ignored.
+fn read_list_file(path: &str) -> Result<Vec<String>, String> {
+    let content = if path == "-" {
+        let mut buf = String::new();
+        io::stdin()
+            .read_to_string(&mut buf)
+            .map_err(|e| format!("cannot read --list-file from stdin: {e}"))?;
+        buf
+    } else {
+        std::fs::read_to_string(path).map_err(|e| format!("cannot read --list-file {path}: {e}"))?
+    };
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Read glob patterns from an exclude list file: one pattern per line,
+/// blank lines and lines starting with `#` are ignored.
+fn read_exclude_list_file(path: &str) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read --exclude-list-file {path}: {e}"))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Build the glob matcher for `--exclude-glob` / `--exclude-list-file`.
+///
+/// Patterns are matched against paths relative to the scan root, using `/` as separator.
+/// `*` does not cross directory boundaries; use `**` for that. A pattern without `/`
+/// (e.g. `*.min.js`) matches at any depth, like in `.gitignore`.
+fn build_exclude_globs(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for p in patterns {
+        // `vendor/` means the directory itself.
+        let p = p.trim_end_matches('/');
+        let anywhere = (!p.contains('/')).then(|| format!("**/{p}"));
+        for p in std::iter::once(p).chain(anywhere.as_deref()) {
+            let glob = GlobBuilder::new(p)
+                .literal_separator(true)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("invalid exclude pattern {p}: {e}"))?;
+            builder.add(glob);
+        }
+    }
+    builder
+        .build()
+        .map_err(|e| format!("invalid exclude patterns: {e}"))
+}
+
+/// Lowercased extension of `path`, or the `FILE_NAMES` key for well-known file names.
+///
+/// A prefix name gives way to a known extension, so `dockerfile_utils.go` is Go.
+fn ext_for_path(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let file_name = path.file_name().and_then(std::ffi::OsStr::to_str)?;
+    let ext = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .map(|s| s.to_ascii_lowercase());
+    let known = FILE_NAMES
+        .iter()
+        .find(|(name, _)| match name.strip_suffix('*') {
+            Some(prefix) => file_name
+                .get(..prefix.len())
+                .is_some_and(|p| p.eq_ignore_ascii_case(prefix)),
+            None => file_name.eq_ignore_ascii_case(name),
+        });
+    match known {
+        Some((name, key))
+            if !name.ends_with('*') || ext.as_deref().and_then(parser_for_ext).is_none() =>
+        {
+            Some((*key).to_string())
+        }
+        _ => ext,
+    }
+}
+
+/// Identity of a file for de-duplicating overlapping roots: the canonical path, so that
+/// `src/a.rs` and `./src/a.rs` match. Falls back to the path as given.
+fn path_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Path of `entry` relative to the scan root, with `/` separators.
+///
+/// When the root itself is a file, the path as given is used (minus a leading `./`).
+fn relative_path(root: &Path, entry: &Path) -> String {
+    let rel = match entry.strip_prefix(root) {
+        Ok(r) if !r.as_os_str().is_empty() => r,
+        _ => entry.strip_prefix(".").unwrap_or(entry),
+    };
+    rel.to_string_lossy().replace('\\', "/")
+}
+
 fn main() {
     let opts = match parse_args() {
         Ok(v) => v,
@@ -204,14 +699,30 @@ fn main() {
         }
     };
 
-    let path = opts.path.as_str();
+    let exclude_globs = match build_exclude_globs(&opts.exclude_globs) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+
+    let roots: Vec<String> = if opts.paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        opts.paths.clone()
+    };
 
     // 用单调时钟计时，避免系统时间跳变导致误差
     let time_start = Instant::now();
 
     // 1) 串行扫描目录，只做轻量过滤（不读文件内容）
     let mut ignore_files: u64 = 0;
-    let mut candidates: Vec<(String, String)> = Vec::new();
+    let mut candidates: Vec<Candidate> = Vec::new();
+    // Archive entries are parsed while the archive is read.
+    let mut archive_parsed: Vec<Option<CodeFileData>> = Vec::new();
+    // Overlapping roots (e.g. `cloc . src`) must not count a file twice.
+    let mut seen: HashSet<PathBuf> = HashSet::new();
 
     // Build a lowercased exclude set for fast checks (case-insensitive on Windows).
     let exclude_dirs: Vec<String> = opts
@@ -220,81 +731,188 @@ fn main() {
         .map(|s| s.to_ascii_lowercase())
         .collect();
 
-    for entry in WalkDir::new(path)
-        .into_iter()
-        .filter_entry(|e| {
-            // Always keep root.
-            if e.depth() == 0 {
-                return true;
+    for path in &roots {
+        // Missing roots (typos, stale `--list-file` entries) are reported, not silently dropped.
+        if let Err(e) = std::fs::symlink_metadata(path) {
+            eprintln!("无法访问 {path}: {e}");
+            ignore_files += 1;
+            continue;
+        }
+
+        // Archives given as roots are read in memory instead of being walked.
+        if opts.extract.contains(path) || (is_archive_path(path) && Path::new(path).is_file()) {
+            if !seen.insert(path_key(Path::new(path))) {
+                continue;
             }
-            // Skip excluded directories.
-            if e.file_type().is_dir() {
-                if let Some(name) = e.file_name().to_str() {
-                    let name_lc = name.to_ascii_lowercase();
-                    return !exclude_dirs.iter().any(|x| x == &name_lc);
+            // Entries are parsed as they are read, so only one is held in memory at a time.
+            let read = read_archive(path, opts.max_bytes, |entry| {
+                let excluded_dir = entry.path.rsplit_once('/').is_some_and(|(dirs, _)| {
+                    dirs.split('/')
+                        .any(|d| exclude_dirs.contains(&d.to_ascii_lowercase()))
+                });
+                if excluded_dir || exclude_globs.is_match(&entry.path) {
+                    return;
+                }
+
+                let Some(ext) = ext_for_path(&entry.path) else {
+                    ignore_files += 1;
+                    return;
+                };
+                if parser_for_ext(ext.as_str()).is_none() {
+                    ignore_files += 1;
+                    return;
+                }
+
+                let candidate = Candidate {
+                    path: format!("{path}!{}", entry.path),
+                    ext,
+                    data: Some(entry.data),
+                };
+                archive_parsed.push(parse_file(&candidate, &opts));
+            });
+            match read {
+                Ok(skipped) => ignore_files += skipped,
+                Err(e) => {
+                    eprintln!("无法读取压缩包 {path}: {e}");
+                    ignore_files += 1;
                 }
             }
-            true
-        })
-        .filter_map(|e| e.ok())
-    {
-        if !entry.file_type().is_file() {
             continue;
         }
 
-        let Some(f_path) = entry.path().to_str() else {
-            ignore_files += 1;
-            continue;
-        };
+        for entry in WalkDir::new(path)
+            .into_iter()
+            .filter_entry(|e| {
+                // Always keep root directories; a root file is still subject to exclude globs.
+                if e.depth() == 0 && !e.file_type().is_file() {
+                    return true;
+                }
+                // Skip excluded directories.
+                if e.file_type().is_dir()
+                    && let Some(name) = e.file_name().to_str()
+                    && exclude_dirs.contains(&name.to_ascii_lowercase())
+                {
+                    return false;
+                }
+                // Skip anything matching an exclude glob (relative to root).
+                exclude_globs.is_empty()
+                    || !exclude_globs.is_match(relative_path(Path::new(path), e.path()))
+            })
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
 
-        let ext_opt = Path::new(f_path)
-            .extension()
-            .and_then(std::ffi::OsStr::to_str)
-            .map(|s| s.to_ascii_lowercase());
+            let Some(f_path) = entry.path().to_str() else {
+                ignore_files += 1;
+                continue;
+            };
 
-        let Some(ext) = ext_opt else {
-            ignore_files += 1;
-            continue;
-        };
+            let Some(ext) = ext_for_path(f_path) else {
+                ignore_files += 1;
+                continue;
+            };
 
-        // Single source of truth: decide parser from extension.
-        let Some(_kind) = parser_for_ext(ext.as_str()) else {
-            ignore_files += 1;
-            continue;
-        };
+            // Single source of truth: decide parser from extension.
+            let Some(_kind) = parser_for_ext(ext.as_str()) else {
+                ignore_files += 1;
+                continue;
+            };
+
+            if !seen.insert(path_key(entry.path())) {
+                continue;
+            }
 
-        candidates.push((f_path.to_owned(), ext));
+            candidates.push(Candidate {
+                path: f_path.to_owned(),
+                ext,
+                data: None,
+            });
+        }
     }
 
     // 2) 解析文件：可并行/可串行
     let parsed: Vec<Option<CodeFileData>> = if opts.parallel {
         candidates
             .par_iter()
-            .map(|(p, ext)| parse_file(p.as_str(), ext.as_str(), &opts))
+            .map(|c| parse_file(c, &opts))
             .collect()
     } else {
-        candidates
-            .iter()
-            .map(|(p, ext)| parse_file(p.as_str(), ext.as_str(), &opts))
-            .collect()
+        candidates.iter().map(|c| parse_file(c, &opts)).collect()
     };
 
     // 3) 合并结果
     let mut code_file_list: Vec<CodeFileData> = Vec::new();
-    for item in parsed {
+    for item in parsed.into_iter().chain(archive_parsed) {
         match item {
             Some(cfd) => code_file_list.push(cfd),
             None => ignore_files += 1,
         }
     }
 
+    // Byte-identical files are counted once, unless --count-duplicates.
+    let duplicates = find_duplicates(&code_file_list);
+    let duplicate_groups: Vec<Vec<String>> = duplicates
+        .iter()
+        .map(|g| {
+            g.iter()
+                .map(|&i| code_file_list[i].path().to_string())
+                .collect()
+        })
+        .collect();
+    let mut duplicate_files: u64 = 0;
+    if !opts.count_duplicates {
+        // Drop by index: the same path may legitimately appear more than once.
+        let dropped: HashSet<usize> = duplicates
+            .iter()
+            .flat_map(|g| g[1..].iter().copied())
+            .collect();
+        duplicate_files = dropped.len() as u64;
+        let mut index = 0;
+        code_file_list.retain(|_| {
+            index += 1;
+            !dropped.contains(&(index - 1))
+        });
+    }
+
+    // Generated files: drop them, or keep them for a separate "generated" row.
+    let mut generated_files: u64 = 0;
+    if opts.generated == ReportMode::Exclude {
+        let before = code_file_list.len();
+        code_file_list.retain(|f| !f.is_generated());
+        generated_files = (before - code_file_list.len()) as u64;
+    }
+
+    // Minified / bundled assets: same choices as generated files.
+    let minified_list: Vec<(String, u64, u64)> = code_file_list
+        .iter()
+        .filter(|f| f.is_minified())
+        .map(|f| {
+            (
+                f.patten().to_string(),
+                f.bytes(),
+                f.blank() + f.comment() + f.code(),
+            )
+        })
+        .collect();
+    if opts.minified == ReportMode::Exclude {
+        code_file_list.retain(|f| !f.is_minified());
+    }
+
     let code_files = code_file_list.len() as u64;
 
     let mut map: HashMap<String, (u64, u64, u64, u64)> = HashMap::new();
     let mut sum: (u64, u64, u64, u64) = (0, 0, 0, 0);
 
     for cfi in &code_file_list {
-        let key = cfi.patten();
+        let key = if cfi.is_generated() && opts.generated == ReportMode::Separate {
+            "generated"
+        } else if cfi.is_minified() && opts.minified == ReportMode::Separate {
+            "minified"
+        } else {
+            cfi.patten()
+        };
 
         let v = map.entry(String::from(key)).or_insert((0, 0, 0, 0));
         v.0 += 1;
@@ -306,6 +924,24 @@ fn main() {
         sum.1 += cfi.blank();
         sum.2 += cfi.comment();
         sum.3 += cfi.code();
+
+        // Embedded code adds lines (not files) to its own language row,
+        // unless the whole file is already in a special bucket.
+        for (lang, blank, comment, code) in cfi.embedded() {
+            let emb_key = if key == cfi.patten() {
+                lang.as_str()
+            } else {
+                key
+            };
+            let v = map.entry(String::from(emb_key)).or_insert((0, 0, 0, 0));
+            v.1 += blank;
+            v.2 += comment;
+            v.3 += code;
+
+            sum.1 += blank;
+            sum.2 += comment;
+            sum.3 += code;
+        }
     }
 
     let time_used = time_start.elapsed().as_millis();
@@ -314,14 +950,32 @@ fn main() {
     println!("Time used: {time_used} ms");
     println!("{:>10} code files", code_files);
     println!("{:>10} files ignored", ignore_files);
+    if !opts.count_duplicates {
+        println!("{:>10} duplicate files", duplicate_files);
+    }
+    if opts.generated == ReportMode::Exclude {
+        println!("{:>10} generated files", generated_files);
+    }
+    if opts.minified == ReportMode::Exclude {
+        println!("{:>10} minified files", minified_list.len());
+    }
     println!();
 
+    if opts.report_duplicates {
+        show_duplicates(&duplicate_groups);
+    }
+
     show_version();
+
+    if opts.by_file {
+        show_by_file(&code_file_list);
+    }
+
     show_header();
 
     // Print in alphabetical order by language
-    let mut rows: Vec<(&String, &(u64, u64, u64, u64))> = map.iter().collect();
-    rows.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut rows: Vec<_> = map.iter().collect();
+    rows.sort_by_key(|(a, _)| a.to_ascii_lowercase());
     for (key, value) in rows {
         println!(
             "{:<W$} {:>W$} {:>W$} {:>W$} {:>W$}",
@@ -345,184 +999,258 @@ fn main() {
         W = 15
     );
     show_dash_line();
+
+    if opts.minified_bytes {
+        show_minified_bytes(&minified_list);
+    }
 }
 
-fn parse_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    let kind = parser_for_ext(ext)?;
-
-    // Read & parse file (respect CLI options)
-    match kind {
-        ParserKind::CLike => parse_code_file(path, ext, opts),
-        ParserKind::Python => parse_python_file(path, ext, opts),
-        ParserKind::Lua => parse_lua_file(path, ext, opts),
-        ParserKind::Xml => parse_xml_file(path, ext, opts),
-        ParserKind::Css => parse_css_file(path, ext, opts),
-        ParserKind::Batch => parse_batch_file(path, ext, opts),
-        ParserKind::PlainText => parse_plain_text_file(path, ext, opts),
-        ParserKind::Sql => parse_sql_file(path, ext, opts),
+fn parse_file(candidate: &Candidate, opts: &CliOptions) -> Option<CodeFileData> {
+    let path = candidate.path.as_str();
+    let kind = parser_for_ext(candidate.ext.as_str())?;
+    let ext = language_for_ext(candidate.ext.as_str());
+
+    // Read file (respect CLI options); archive entries are already in memory.
+    let owned;
+    let buf: &[u8] = match &candidate.data {
+        Some(buf) => buf,
+        None => {
+            owned = read_file_bytes(path, opts.max_bytes).ok()?;
+            &owned
+        }
+    };
+    let content = decode_bytes(path, buf, opts.binary_skip).ok()?;
+    let content = content.as_str();
+
+    let mut cfd = match kind {
+        // Hosts: regions, fences and notebook cells need more than one line classifier.
+        ParserKind::Html => parse_html_file(path, ext, content, opts.split_embedded),
+        ParserKind::Markdown => parse_markdown_file(path, ext, content, opts.split_embedded),
+        ParserKind::Notebook => parse_notebook_file(path, ext, content),
+        // Classifiers configured per file rather than per kind.
+        ParserKind::Sql => {
+            let dialect = match candidate.ext.as_str() {
+                "mysql" => SqlDialect::MySql,
+                "pgsql" => SqlDialect::Postgres,
+                "tsql" => SqlDialect::TSql,
+                _ => opts.sql_dialect,
+            };
+            let classifier = Classifier::Sql(SqlState::new(dialect));
+            parse_with_classifier(path, ext, content, classifier)
+        }
+        ParserKind::Asm => {
+            let state = asm_state(path, content);
+            parse_with_classifier(path, ext, content, Classifier::Asm(state))
+        }
+        _ => parse_with_classifier(path, ext, content, Classifier::for_kind(kind)?),
+    };
+    // Hashing is only worth it when duplicates are dropped or listed.
+    let digest =
+        (!opts.count_duplicates || opts.report_duplicates).then(|| Sha256::digest(buf).into());
+    cfd.set_content(digest, buf.len() as u64);
+    let asset = matches!(kind, ParserKind::JavaScript | ParserKind::Css);
+    if is_minified(path, content, asset) {
+        cfd.set_minified();
     }
+    Some(cfd)
 }
 
 // ------------------------
 // Generic parsing helpers
 // ------------------------
 
-fn parse_with_state<S>(
+/// Count `content` line by line. The classifier may also name an embedded language for a line
+/// (e.g. `Some("js")` inside an HTML `<script>`); such lines are credited to that language.
+/// Generator banners are looked for in comments only when `comment_markers` is set. A
+/// non-blank line with neither code nor comment counts as code, or as blank with
+/// `unclassified_blank` (COBOL lines that only hold a sequence number).
+fn parse_with_regions<S>(
     path: &str,
     ext: &str,
-    opts: &CliOptions,
+    content: &str,
     mut state: S,
-    mut classify: impl FnMut(&str, &mut S) -> (bool, bool),
-) -> Option<CodeFileData> {
+    comment_markers: bool,
+    unclassified_blank: bool,
+    mut classify: impl FnMut(&str, &mut S) -> (bool, bool, Option<&'static str>),
+) -> CodeFileData {
     let mut cfd = CodeFileData::new(String::from(path), String::from(ext));
-    let result = read_non_utf8_lines(path, opts.max_bytes, opts.binary_skip);
-    let Ok(content) = result else {
-        return None;
-    };
 
     cfd.set_lines(content.lines().count() as u64);
-
-    for line in content.lines() {
-        if line.trim().is_empty() {
-            cfd.add_blank();
+    let lock_file = is_lock_file(path);
+
+    for (line_no, line) in content.lines().enumerate() {
+        // Blank lines go through the classifier too, so ones inside a region follow its language.
+        let (saw_code, saw_comment, lang) = classify(line, &mut state);
+        if !saw_code && !saw_comment && (unclassified_blank || line.trim().is_empty()) {
+            match lang {
+                Some(lang) => cfd.add_embedded_blank(language_for_ext(lang)),
+                None => cfd.add_blank(),
+            }
             continue;
         }
-
-        let (saw_code, saw_comment) = classify(line, &mut state);
-        if saw_comment {
-            cfd.add_comment();
+        let saw_code = saw_code || !saw_comment;
+        let in_comment = saw_comment && comment_markers;
+        if line_no < GENERATED_SCAN_LINES && is_generated_marker(line, in_comment, lock_file) {
+            cfd.set_generated();
         }
-        if saw_code {
-            cfd.add_code();
-        }
-        if !saw_code && !saw_comment {
-            cfd.add_code();
-        }
-    }
-
-    Some(cfd)
-}
-
-fn parse_no_state(
-    path: &str,
-    ext: &str,
-    opts: &CliOptions,
-    mut classify: impl FnMut(&str) -> (bool, bool),
-) -> Option<CodeFileData> {
-    let mut cfd = CodeFileData::new(String::from(path), String::from(ext));
-    let result = read_non_utf8_lines(path, opts.max_bytes, opts.binary_skip);
-    let Ok(content) = result else {
-        return None;
-    };
-
-    cfd.set_lines(content.lines().count() as u64);
-
-    for line in content.lines() {
-        if line.trim().is_empty() {
-            cfd.add_blank();
+        if let Some(lang) = lang {
+            cfd.add_embedded(language_for_ext(lang), saw_code, saw_comment);
             continue;
         }
-
-        let (saw_code, saw_comment) = classify(line);
         if saw_comment {
             cfd.add_comment();
         }
         if saw_code {
             cfd.add_code();
         }
-        if !saw_code && !saw_comment {
-            cfd.add_code();
-        }
-    }
-
-    Some(cfd)
-}
-
-fn parse_plain_text_file_shared(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    let mut cfd = CodeFileData::new(String::from(path), String::from(ext));
-    let result = read_non_utf8_lines(path, opts.max_bytes, opts.binary_skip);
-    let Ok(content) = result else {
-        return None;
-    };
-
-    cfd.set_lines(content.lines().count() as u64);
-
-    for line in content.lines() {
-        if line.trim().is_empty() {
-            cfd.add_blank();
-        } else {
-            cfd.add_code();
-        }
     }
 
-    Some(cfd)
+    cfd
 }
 
-// 使用//和/* */注释规则
-fn parse_code_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_with_state(path, ext, opts, ParseState::new(), |line, state| {
-        classify_line_c_like(line, state)
-    })
+/// Count a file with the line classifier of its `ParserKind`.
+fn parse_with_classifier(
+    path: &str,
+    ext: &str,
+    content: &str,
+    classifier: Classifier,
+) -> CodeFileData {
+    let cobol = matches!(classifier, Classifier::Cobol);
+    parse_with_regions(
+        path,
+        ext,
+        content,
+        classifier,
+        true,
+        cobol,
+        |line, classifier| {
+            // Blank lines still reach the classifier, e.g. to end a `\`-continued C++ comment.
+            let (saw_code, saw_comment) = classifier.classify(line);
+            if line.trim().is_empty() {
+                return (false, false, None);
+            }
+            (saw_code, saw_comment, None)
+        },
+    )
 }
 
-// python 使用#和""" """注释规则
-fn parse_python_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_with_state(path, ext, opts, PythonState::new(), |line, state| {
-        classify_line_python_like(line, state)
+// html/vue/svelte: markup `<!-- -->`, with JavaScript `<script>` and CSS `<style>` regions
+fn parse_html_file(path: &str, ext: &str, content: &str, split_embedded: bool) -> CodeFileData {
+    let state = HtmlState::new();
+    parse_with_regions(path, ext, content, state, true, false, |line, state| {
+        let (saw_code, saw_comment) = classify_line_html_like(line, state);
+        let lang = if split_embedded {
+            state.line_lang
+        } else {
+            None
+        };
+        (saw_code, saw_comment, lang)
     })
 }
 
-// lua 使用--和--[[ ]]注释规则
-fn parse_lua_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_with_state(path, ext, opts, LuaState::new(), |line, state| {
-        classify_line_lua_like(line, state)
-    })
+// assembly: dialect from the extension (`.nasm`) or the content; `.S`/`.sx` allow cpp directives
+fn asm_state(path: &str, content: &str) -> AsmState {
+    // Extensions are lowercased for `PATTERNS`, but `.s` vs `.S` matters here.
+    let raw_ext = Path::new(path)
+        .extension()
+        .and_then(std::ffi::OsStr::to_str);
+    let preprocessed = matches!(raw_ext, Some("S" | "sx"));
+    let dialect = if raw_ext.is_some_and(|e| e.eq_ignore_ascii_case("nasm")) {
+        AsmDialect::Nasm
+    } else {
+        AsmDialect::detect(content)
+    };
+    AsmState::new(dialect, preprocessed)
 }
 
-// xml、html 使用<!-- -->注释规则
-fn parse_xml_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_with_state(path, ext, opts, ParseState::new(), |line, state| {
-        classify_line_xml_like(line, state)
+// markdown: prose and `<!-- -->` as comment; fenced blocks use the classifier of their tag
+fn parse_markdown_file(path: &str, ext: &str, content: &str, split_embedded: bool) -> CodeFileData {
+    // Classifier for the open fence, created on its first body line.
+    let mut fence: Option<(Option<&'static str>, Classifier)> = None;
+    // Prose counts as comment, so "generated" or "do not edit" in it is no banner.
+    let state = MarkdownState::new();
+    parse_with_regions(path, ext, content, state, false, false, |line, state| {
+        let (saw_code, saw_comment) = classify_line_markdown_like(line, state);
+        if !state.in_fence_body {
+            fence = None;
+            return (saw_code, saw_comment, None);
+        }
+        let (fence_ext, classifier) = fence.get_or_insert_with(|| {
+            let fence_ext = state.fence_tag.as_deref().and_then(ext_for_language_name);
+            let classifier = fence_ext
+                .and_then(parser_for_ext)
+                .and_then(Classifier::for_kind)
+                .unwrap_or(Classifier::PlainText);
+            (fence_ext, classifier)
+        });
+        let (saw_code, saw_comment) = classifier.classify(line);
+        (saw_code, saw_comment, fence_ext.filter(|_| split_embedded))
     })
 }
 
-// css, 使用/* */注释规则
-fn parse_css_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_with_state(path, ext, opts, ParseState::new(), |line, state| {
-        classify_line_css_like(line, state)
-    })
-}
+// jupyter: code cells use the kernel language's rules, markdown cells count as comment;
+// raw cells, outputs and metadata are not counted. Invalid notebooks are counted as JSON.
+fn parse_notebook_file(path: &str, ext: &str, content: &str) -> CodeFileData {
+    let Some(notebook) = parse_notebook(content) else {
+        let classifier = Classifier::CLike(ParseState::new());
+        return parse_with_classifier(path, ext, content, classifier);
+    };
+    let kernel = ext_for_language_name(&notebook.language).and_then(parser_for_ext);
 
-// windows batch/cmd: comment line via REM / ::
-fn parse_batch_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_no_state(path, ext, opts, |line| classify_line_batch_like(line))
-}
+    let mut cfd = CodeFileData::new(String::from(path), String::from(ext));
+    let mut lines = 0u64;
+
+    for cell in notebook.cells.iter().filter(|c| c.kind != CellKind::Raw) {
+        let mut classifier = kernel
+            .and_then(Classifier::for_kind)
+            .unwrap_or(Classifier::PlainText);
+        for line in cell.source.lines() {
+            lines += 1;
+            if line.trim().is_empty() {
+                // The classifier still sees it, e.g. to end a `\`-continued C++ comment.
+                if cell.kind == CellKind::Code {
+                    classifier.classify(line);
+                }
+                cfd.add_blank();
+                continue;
+            }
+            if cell.kind == CellKind::Markdown {
+                cfd.add_comment();
+                continue;
+            }
 
-// plain text: do not parse comments; just count blanks and non-empty as code
-fn parse_plain_text_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_plain_text_file_shared(path, ext, opts)
-}
+            let (saw_code, saw_comment) = classifier.classify(line);
+            if saw_comment {
+                cfd.add_comment();
+            }
+            if saw_code || !saw_comment {
+                cfd.add_code();
+            }
+        }
+    }
 
-// sql: supports `--` line comments, and `/* */` block comments
-fn parse_sql_file(path: &str, ext: &str, opts: &CliOptions) -> Option<CodeFileData> {
-    parse_with_state(path, ext, opts, ParseState::new(), |line, state| {
-        classify_line_sql_like(line, state)
-    })
+    cfd.set_lines(lines);
+    cfd
 }
 
-fn read_non_utf8_lines(path: &str, max_bytes: u64, binary_skip: bool) -> io::Result<String> {
+fn read_file_bytes(path: &str, max_bytes: u64) -> io::Result<Vec<u8>> {
     let file = File::open(path)?;
 
-    if let Ok(meta) = file.metadata() {
-        if meta.len() > max_bytes {
-            return Err(io::Error::new(ErrorKind::InvalidData, "文件过大，已跳过"));
-        }
+    if let Ok(meta) = file.metadata()
+        && meta.len() > max_bytes
+    {
+        return Err(io::Error::new(ErrorKind::InvalidData, "文件过大，已跳过"));
     }
 
     let mut reader = BufReader::new(file);
     let mut buf = Vec::new();
     reader.read_to_end(&mut buf)?;
 
+    Ok(buf)
+}
+
+/// Decode raw file bytes to text: UTF-8 first, then a detected legacy encoding.
+fn decode_bytes(path: &str, buf: &[u8], binary_skip: bool) -> io::Result<String> {
     if binary_skip {
         // Heuristic: skip likely-binary files early (NUL byte is a strong signal).
         if buf.iter().take(8192).any(|&b| b == 0) {
@@ -533,14 +1261,14 @@ fn read_non_utf8_lines(path: &str, max_bytes: u64, binary_skip: bool) -> io::Res
         }
     }
 
-    if let Ok(s) = std::str::from_utf8(&buf) {
+    if let Ok(s) = std::str::from_utf8(buf) {
         return Ok(s.to_owned());
     }
 
-    let charset = detect(&buf);
+    let charset = detect(buf);
     let enc_label = charset.0;
     if let Some(enc) = encoding_from_whatwg_label(enc_label.as_str()) {
-        match enc.decode(&buf, DecoderTrap::Replace) {
+        match enc.decode(buf, DecoderTrap::Replace) {
             Ok(content) => return Ok(content),
             Err(_) => eprintln!("解码失败: {}", path),
         }
@@ -1,9 +1,19 @@
 //! 数据结构体
 
+/// SHA-256 of a file's raw bytes.
+pub type Digest = [u8; 32];
 
 #[derive(Clone, Copy)]
 pub enum ParserKind {
     CLike,
+    /// C / C++: C-like plus raw strings, digit separators and continued `//` comments.
+    Cpp,
+    /// Go: C-like plus `` ` `` raw strings.
+    Go,
+    /// C#: C-like plus `@"..."` verbatim and `"""` raw strings.
+    CSharp,
+    /// JavaScript / TypeScript: C-like comments, template literals and regex literals.
+    JavaScript,
     Python,
     Lua,
     Xml,
@@ -12,24 +22,121 @@ pub enum ParserKind {
     Batch,
     /// Plain text: no comment syntax; non-empty lines are counted as code.
     PlainText,
-    /// SQL files: line comments via `--`, block comments via `/* */`.
+    /// SQL files: `--` and `/* */` comments, plus per-dialect rules (see `SqlDialect`).
     Sql,
+    /// Shell scripts: word-initial `#` comments, multi-line quotes and heredocs.
+    Shell,
+    /// YAML: `#` comments, multi-line quoted scalars and `|` / `>` block scalars.
+    Yaml,
+    /// TOML: `#` comments, multi-line basic/literal strings.
+    Toml,
+    /// HTML, Vue and Svelte: markup with embedded `<script>` / `<style>` regions.
+    Html,
+    /// Markdown: prose as documentation, fenced code blocks as code.
+    Markdown,
+    /// Jupyter notebook: cells extracted from the JSON.
+    Notebook,
+    /// PHP: `//`, `#`, `/* */`, heredoc/nowdoc; inline HTML outside `<?php ?>`.
+    Php,
+    /// Ruby: `#`, `=begin`/`=end`, heredocs, `__END__`.
+    Ruby,
+    /// Perl: `#`, POD, heredocs, `__END__`/`__DATA__`.
+    Perl,
+    /// Haskell / Elm: `--` and nesting `{- -}`.
+    Haskell,
+    /// OCaml: nesting `(* *)`.
+    OCaml,
+    /// F#: `//` and nesting `(* *)`.
+    FSharp,
+    /// C-like with nesting `/* */` (Swift, Kotlin, Scala, Dart).
+    CNested,
+    /// Clojure: `;`, `#_` discard, `(comment ...)` forms.
+    Clojure,
+    /// Scheme / Racket: `;`, nesting `#| |#`, `#;` datum comments.
+    Scheme,
+    /// Common Lisp / Emacs Lisp: `;`, nesting `#| |#`.
+    Lisp,
+    /// Fixed-form Fortran: column 1 comment marks, `!`.
+    FortranFixed,
+    /// Free-form Fortran: `!`.
+    FortranFree,
+    /// COBOL: column 7 indicator, `*>`.
+    Cobol,
+    /// Assembly: GAS, ARM or NASM comment rules, detected per file.
+    Asm,
+    /// PowerShell: `#`, `<# #>`, here-strings.
+    PowerShell,
+    /// Visual Basic / VBScript: `'` and `REM`.
+    VisualBasic,
+    /// HCL / Terraform: `#`, `//`, `/* */`, heredocs.
+    Hcl,
+    /// Dockerfile: `#` at line start.
+    Dockerfile,
+    /// Makefile: `#` on make lines, shell rules on recipe lines.
+    Makefile,
+    /// Nix: `#`, `/* */`, `''` strings.
+    Nix,
 }
 
+/// How to report a class of special files (e.g. generated code).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportMode {
+    /// Count them like any other file.
+    Include,
+    /// Count them in their own row instead of their language.
+    Separate,
+    /// Leave them out of the report.
+    Exclude,
+}
+
+/// Comment and string rules for SQL files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// Standard SQL: `--`, `/* */`, `''` escapes and no backslash escapes.
+    #[default]
+    Ansi,
+    /// MySQL / MariaDB: also `#` comments, backslash escapes and `` `...` `` identifiers.
+    MySql,
+    /// PostgreSQL: nesting `/* */`, `$$` / `$tag$` quoting and `E'...'` escape strings.
+    Postgres,
+    /// T-SQL (SQL Server): nesting `/* */` and `[...]` identifiers.
+    TSql,
+}
 
 #[derive(Debug, Clone)]
-pub(crate) struct CliOptions {
-    pub(crate) path: String,
-    pub(crate) parallel: bool,
-    pub(crate) max_bytes: u64,
-    pub(crate) binary_skip: bool,
-    pub(crate) exclude_dirs: Vec<String>,
+pub struct CliOptions {
+    /// Directories and/or files to scan; empty means the current directory.
+    pub paths: Vec<String>,
+    pub parallel: bool,
+    pub max_bytes: u64,
+    pub binary_skip: bool,
+    pub exclude_dirs: Vec<String>,
+    /// Glob patterns matched against paths relative to the scan root.
+    pub exclude_globs: Vec<String>,
+    /// Paths to always read as archives (`--extract`), whatever their extension.
+    pub extract: Vec<String>,
+    /// Print a per-file table in addition to the per-language summary.
+    pub by_file: bool,
+    /// Count byte-identical files separately instead of once per run.
+    pub count_duplicates: bool,
+    /// List groups of byte-identical files.
+    pub report_duplicates: bool,
+    /// What to do with files carrying a generator banner.
+    pub generated: ReportMode,
+    /// What to do with minified / bundled assets.
+    pub minified: ReportMode,
+    /// Print bytes alongside lines for minified files.
+    pub minified_bytes: bool,
+    /// Credit embedded code (e.g. `<script>` in HTML) to its own language instead of the host.
+    pub split_embedded: bool,
+    /// Dialect for `.sql` files; `.mysql`, `.pgsql` and `.tsql` always use their own.
+    pub sql_dialect: SqlDialect,
 }
 
 impl Default for CliOptions {
     fn default() -> Self {
         Self {
-            path: ".".to_string(),
+            paths: Vec::new(),
             parallel: true,
             max_bytes: 16 * 1024 * 1024, // 16MiB
             binary_skip: true,
@@ -38,26 +145,58 @@ impl Default for CliOptions {
                 "target".to_string(),
                 "node_modules".to_string(),
             ],
+            exclude_globs: Vec::new(),
+            extract: Vec::new(),
+            by_file: false,
+            count_duplicates: false,
+            report_duplicates: false,
+            generated: ReportMode::Separate,
+            minified: ReportMode::Separate,
+            minified_bytes: false,
+            split_embedded: false,
+            sql_dialect: SqlDialect::Ansi,
         }
     }
 }
 
+/// A file queued for parsing.
+pub struct Candidate {
+    /// Display path; archive entries look like `release.tar.gz!src/main.c`.
+    pub path: String,
+    pub ext: String,
+    /// Contents already in memory (archive entries); `None` means read from `path`.
+    pub data: Option<Vec<u8>>,
+}
+
 pub struct CodeFileData {
+    path: String,
     patten: String,
     lines: u64,
     blank: u64,
     comment: u64,
     code: u64,
+    digest: Option<Digest>,
+    bytes: u64,
+    generated: bool,
+    minified: bool,
+    /// Lines credited to embedded languages: (language, blank, comment, code).
+    embedded: Vec<(String, u64, u64, u64)>,
 }
 
 impl CodeFileData {
     pub fn new(path: String, patten: String) -> CodeFileData {
         CodeFileData {
+            path,
             patten,
             lines: 0,
             blank: 0,
             comment: 0,
             code: 0,
+            digest: None,
+            bytes: 0,
+            generated: false,
+            minified: false,
+            embedded: Vec::new(),
         }
     }
     pub fn add_blank(&mut self) {
@@ -72,10 +211,48 @@ impl CodeFileData {
         self.code += 1;
     }
 
+    /// Count a non-blank line for an embedded language instead of the host.
+    pub fn add_embedded(&mut self, lang: &str, saw_code: bool, saw_comment: bool) {
+        let v = self.embedded_entry(lang);
+        if saw_comment {
+            v.2 += 1;
+        }
+        if saw_code || !saw_comment {
+            v.3 += 1;
+        }
+    }
+
+    /// Count a blank line inside an embedded region for that language instead of the host.
+    pub fn add_embedded_blank(&mut self, lang: &str) {
+        self.embedded_entry(lang).1 += 1;
+    }
+
+    fn embedded_entry(&mut self, lang: &str) -> &mut (String, u64, u64, u64) {
+        let idx = match self.embedded.iter().position(|(l, ..)| l == lang) {
+            Some(idx) => idx,
+            None => {
+                self.embedded.push((lang.to_string(), 0, 0, 0));
+                self.embedded.len() - 1
+            }
+        };
+        &mut self.embedded[idx]
+    }
+
     pub fn set_lines(&mut self, lines: u64) {
         self.lines = lines;
     }
 
+    /// Record the raw content digest and size, used for duplicate detection.
+    /// Files without a digest are never reported as duplicates.
+    pub fn set_content(&mut self, digest: Option<Digest>, bytes: u64) {
+        self.digest = digest;
+        self.bytes = bytes;
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
     pub fn patten(&self) -> &str {
         &self.patten
     }
@@ -91,4 +268,32 @@ impl CodeFileData {
     pub fn code(&self) -> u64 {
         self.code
     }
+
+    pub fn embedded(&self) -> &[(String, u64, u64, u64)] {
+        &self.embedded
+    }
+
+    pub fn set_generated(&mut self) {
+        self.generated = true;
+    }
+
+    pub fn is_generated(&self) -> bool {
+        self.generated
+    }
+
+    pub fn set_minified(&mut self) {
+        self.minified = true;
+    }
+
+    pub fn is_minified(&self) -> bool {
+        self.minified
+    }
+
+    pub fn digest(&self) -> Option<&Digest> {
+        self.digest.as_ref()
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
 }
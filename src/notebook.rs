@@ -0,0 +1,71 @@
+//! Cell extraction for Jupyter notebooks (`.ipynb`).
+//!
+//! Only the cell sources are kept; outputs, execution counts and metadata are
+//! dropped so that they never reach the line counters.
+
+use serde_json::Value;
+
+/// Kernel language assumed when the notebook does not declare one.
+const DEFAULT_LANGUAGE: &str = "python";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Code,
+    Markdown,
+    /// Raw cells are passed through unrendered (e.g. LaTeX for nbconvert).
+    Raw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotebookCell {
+    pub kind: CellKind,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notebook {
+    /// Kernel language, lowercased (e.g. `python`, `r`, `julia`).
+    pub language: String,
+    pub cells: Vec<NotebookCell>,
+}
+
+/// Parse notebook JSON (nbformat 4). Returns `None` if `content` is not a notebook.
+pub fn parse_notebook(content: &str) -> Option<Notebook> {
+    let root: Value = serde_json::from_str(content).ok()?;
+    let cells = root.get("cells")?.as_array()?;
+
+    let metadata = root.get("metadata");
+    let language = metadata
+        .and_then(|m| m.pointer("/kernelspec/language"))
+        .or_else(|| metadata.and_then(|m| m.pointer("/language_info/name")))
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_LANGUAGE)
+        .to_ascii_lowercase();
+
+    let cells = cells
+        .iter()
+        .filter_map(|cell| {
+            let kind = match cell.get("cell_type")?.as_str()? {
+                "code" => CellKind::Code,
+                "markdown" => CellKind::Markdown,
+                "raw" => CellKind::Raw,
+                _ => return None,
+            };
+            Some(NotebookCell {
+                kind,
+                source: cell_source(cell.get("source")?),
+            })
+        })
+        .collect();
+
+    Some(Notebook { language, cells })
+}
+
+/// `source` is either one string or a list of line strings (each keeping its `\n`).
+fn cell_source(source: &Value) -> String {
+    match source {
+        Value::String(s) => s.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use cloc::archive::read_archive;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+/// Write `bytes` to a per-test file in the temp dir and return its path.
+fn fixture(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("cloc-{}-{name}", std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn tar_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut dir = tar::Header::new_gnu();
+    dir.set_entry_type(tar::EntryType::Directory);
+    dir.set_size(0);
+    builder.append_data(&mut dir, "src/", &[][..]).unwrap();
+    for (name, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        builder.append_data(&mut header, name, *data).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+fn read_all(path: &Path, max_bytes: u64) -> (Vec<(String, Vec<u8>)>, u64) {
+    let mut entries = Vec::new();
+    let skipped = read_archive(path.to_str().unwrap(), max_bytes, |e| {
+        entries.push((e.path, e.data))
+    })
+    .unwrap();
+    (entries, skipped)
+}
+
+#[test]
+fn tar_nested_paths_and_oversized_entries() {
+    let tar = tar_bytes(&[("src/deep/a.rs", b"fn a() {}\n"), ("big.txt", &[b'x'; 64])]);
+    let path = fixture("nested.tar", &tar);
+
+    let (entries, skipped) = read_all(&path, 32);
+    assert_eq!(
+        entries,
+        vec![("src/deep/a.rs".to_string(), b"fn a() {}\n".to_vec())]
+    );
+    assert_eq!(skipped, 1);
+}
+
+#[test]
+fn gzip_tar_detected_by_magic_bytes() {
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&tar_bytes(&[("lib/b.py", b"x = 1\n")]))
+        .unwrap();
+    let path = fixture("release.bin", &gz.finish().unwrap());
+
+    let (entries, skipped) = read_all(&path, 1024);
+    assert_eq!(entries, vec![("lib/b.py".to_string(), b"x = 1\n".to_vec())]);
+    assert_eq!(skipped, 0);
+}
+
+#[test]
+fn zip_detected_by_magic_bytes() {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    zip.add_directory("pkg/", options).unwrap();
+    zip.start_file("pkg/sub/c.js", options).unwrap();
+    zip.write_all(b"let c = 1;\n").unwrap();
+    zip.start_file("huge.js", options).unwrap();
+    zip.write_all(&[b'/'; 64]).unwrap();
+    let path = fixture("drop.dat", &zip.finish().unwrap().into_inner());
+
+    let (entries, skipped) = read_all(&path, 32);
+    assert_eq!(
+        entries,
+        vec![("pkg/sub/c.js".to_string(), b"let c = 1;\n".to_vec())]
+    );
+    assert_eq!(skipped, 1);
+}
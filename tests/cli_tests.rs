@@ -0,0 +1,212 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Write `files` (paths may contain `/`) into a fresh per-test directory in the temp dir
+/// and return it.
+fn fixture_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cloc-cli-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+/// Run the binary with `args` in `cwd`.
+fn cloc<I: AsRef<OsStr>>(cwd: &Path, args: impl IntoIterator<Item = I>) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cloc"))
+        .current_dir(cwd)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// The (files, blank, comment, code) row for `language` in a report.
+fn row(out: &Output, language: &str) -> Option<[u64; 4]> {
+    let stdout = std::str::from_utf8(&out.stdout).unwrap();
+    stdout.lines().find_map(|line| {
+        let rest = line.strip_prefix(language)?;
+        if !rest.starts_with(' ') {
+            return None;
+        }
+        let nums: Vec<u64> = rest
+            .split_whitespace()
+            .map(|n| n.parse().ok())
+            .collect::<Option<_>>()?;
+        nums.try_into().ok()
+    })
+}
+
+/// Run the binary on `dir` and return the (files, blank, comment, code) row for `language`.
+fn report_row(dir: &Path, language: &str) -> Option<[u64; 4]> {
+    row(&cloc(dir, ["."]), language)
+}
+
+#[test]
+fn cobol_sequence_area_only_lines_are_blank() {
+    let dir = fixture_dir(
+        "cobol",
+        &[(
+            "a.cob",
+            "000100 IDENTIFICATION DIVISION.\n\
+             000200\n\
+             000300*    COMMENT\n\
+             000400                                                                  SEQ00400\n\
+             000500 PROCEDURE DIVISION.\n",
+        )],
+    );
+    assert_eq!(report_row(&dir, "COBOL"), Some([1, 2, 1, 2]));
+}
+
+#[test]
+fn well_known_file_names_match_case_insensitively() {
+    let dir = fixture_dir(
+        "names",
+        &[
+            ("MAKEFILE", "all:\n\techo hi # greet\n"),
+            ("dockerfile.dev", "# base\nFROM alpine\n"),
+            ("dockerfile_utils.py", "x = 1\n"),
+        ],
+    );
+    assert_eq!(report_row(&dir, "Makefile"), Some([1, 0, 1, 2]));
+    assert_eq!(report_row(&dir, "Dockerfile"), Some([1, 0, 1, 1]));
+    assert_eq!(report_row(&dir, "py"), Some([1, 0, 0, 1]));
+}
+
+#[test]
+fn exclude_globs_without_slash_match_at_any_depth() {
+    let dir = fixture_dir(
+        "exclude-glob",
+        &[
+            ("a.py", "x = 1\n"),
+            ("src/deep/b.py", "x = 1\n"),
+            ("src/app.min.js", "a()\n"),
+            ("src/app.js", "a()\n"),
+            ("gen/c.rs", "fn c() {}\n"),
+            ("src/gen/d.rs", "fn d() {}\n"),
+        ],
+    );
+    let out = cloc(
+        &dir,
+        ["--exclude-glob", "*.py", "--exclude-glob", "*.min.js", "."],
+    );
+    assert_eq!(row(&out, "py"), None);
+    assert_eq!(row(&out, "js"), Some([1, 0, 0, 1]));
+    assert_eq!(row(&out, "rs"), Some([2, 0, 0, 2]));
+
+    // With a `/` the pattern is anchored at the scan root.
+    let out = cloc(&dir, ["--exclude-glob", "gen/*.rs", "."]);
+    assert_eq!(row(&out, "rs"), Some([1, 0, 0, 1]));
+    let out = cloc(&dir, ["--exclude-glob", "src/gen", "."]);
+    assert_eq!(row(&out, "rs"), Some([1, 0, 0, 1]));
+    // A bare directory name (`gen` or `gen/`) is excluded wherever it appears.
+    let out = cloc(&dir, ["--exclude-glob", "gen/", "."]);
+    assert_eq!(row(&out, "rs"), None);
+}
+
+#[test]
+fn exclude_list_file_skips_blank_and_comment_lines() {
+    let dir = fixture_dir(
+        "exclude-list",
+        &[
+            ("a.py", "x = 1\n"),
+            ("src/b.py", "x = 1\n"),
+            ("src/c.rs", "fn c() {}\n"),
+            (".clocignore", "# generated code\n\n  src/*.rs  \n*.PY\n"),
+        ],
+    );
+    let out = cloc(&dir, ["--exclude-list-file", ".clocignore", "."]);
+    assert!(out.status.success());
+    assert_eq!(row(&out, "py"), None);
+    assert_eq!(row(&out, "rs"), None);
+
+    let out = cloc(&dir, ["--exclude-list-file", "missing", "."]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+/// The `N files ignored` count from the report summary.
+fn ignored(out: &Output) -> u64 {
+    let stdout = std::str::from_utf8(&out.stdout).unwrap();
+    stdout
+        .lines()
+        .find_map(|l| l.trim().strip_suffix("files ignored")?.trim().parse().ok())
+        .unwrap()
+}
+
+#[test]
+fn overlapping_roots_count_files_once() {
+    let dir = fixture_dir(
+        "overlap",
+        &[("src/a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\n")],
+    );
+    let out = cloc(&dir, [".", "src", "./src/a.rs", "src/../src"]);
+    assert_eq!(row(&out, "rs"), Some([2, 0, 0, 2]));
+}
+
+#[test]
+fn list_file_skips_blank_lines_and_reports_missing_entries() {
+    let dir = fixture_dir(
+        "list-file",
+        &[
+            ("src/a.rs", "fn a() {}\n"),
+            ("b.rs", "fn b() {}\n"),
+            ("paths.txt", "src/a.rs\n\n   \nb.rs\nmissing.rs\n"),
+        ],
+    );
+    let out = cloc(&dir, ["--list-file", "paths.txt"]);
+    assert!(out.status.success());
+    assert_eq!(row(&out, "rs"), Some([2, 0, 0, 2]));
+    assert_eq!(ignored(&out), 1);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("missing.rs"));
+}
+
+#[test]
+fn missing_root_is_reported() {
+    let dir = fixture_dir("missing-root", &[]);
+    let out = cloc(&dir, ["nonexistent"]);
+    assert_eq!(ignored(&out), 1);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("nonexistent"));
+}
+
+#[test]
+fn duplicates_are_hashed_only_when_dropped_or_reported() {
+    let dir = fixture_dir("duplicates", &[("a.py", "x = 1\n"), ("b.py", "x = 1\n")]);
+    assert_eq!(report_row(&dir, "py"), Some([1, 0, 0, 1]));
+
+    let out = cloc(&dir, [".", "--count-duplicates"]);
+    assert_eq!(row(&out, "py"), Some([2, 0, 0, 2]));
+
+    let out = cloc(&dir, [".", "--count-duplicates", "--report-duplicates"]);
+    assert_eq!(row(&out, "py"), Some([2, 0, 0, 2]));
+    let stdout = std::str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("Duplicate files (1 groups):"), "{stdout}");
+}
+
+#[test]
+fn markdown_prose_and_non_lock_data_are_not_generated() {
+    let dir = fixture_dir(
+        "generated-markers",
+        &[
+            (
+                "README.md",
+                "# Tool\n\nThe output is generated; do not edit it.\n",
+            ),
+            ("config.json", "{\n  \"lockfileVersion\": 2\n}\n"),
+            ("package-lock.json", "{\n  \"lockfileVersion\": 3\n}\n"),
+        ],
+    );
+    let out = cloc(&dir, ["."]);
+    assert_eq!(row(&out, "Markdown"), Some([1, 1, 2, 0]));
+    assert_eq!(row(&out, "json"), Some([1, 0, 0, 3]));
+    assert_eq!(row(&out, "generated"), Some([1, 0, 0, 3]));
+}
+
+#[test]
+fn blank_line_ends_continued_cpp_comment() {
+    let dir = fixture_dir("cpp-continued", &[("a.cpp", "// note \\\n\nint x = 1;\n")]);
+    assert_eq!(report_row(&dir, "cpp"), Some([1, 1, 1, 1]));
+}
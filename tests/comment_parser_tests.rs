@@ -1,9 +1,22 @@
 use cloc::comment_parser::{
-    classify_line_batch_like,
-    classify_line_c_like, classify_line_css_like, classify_line_lua_like, classify_line_python_like,
-    classify_line_xml_like, classify_line_sql_like,
-    LuaState, ParseState, PythonState,
+    AsmDialect, AsmState, Classifier, HclState, HtmlRegion, HtmlState, JsState, LispState,
+    LuaState, MakefileState, MarkdownState, NestedCommentState, NixState, ParseState, PhpState,
+    PowerShellState, PythonState, ScriptState, ShellState, SqlState, TomlState, YamlState,
+    classify_line_asm_like, classify_line_batch_like, classify_line_c_like,
+    classify_line_c_nested_like, classify_line_clojure_like, classify_line_cobol_like,
+    classify_line_cpp_like, classify_line_csharp_like, classify_line_css_like,
+    classify_line_dockerfile_like, classify_line_fortran_fixed_like,
+    classify_line_fortran_free_like, classify_line_fsharp_like, classify_line_go_like,
+    classify_line_haskell_like, classify_line_hcl_like, classify_line_html_like,
+    classify_line_js_like, classify_line_lisp_like, classify_line_lua_like,
+    classify_line_makefile_like, classify_line_markdown_like, classify_line_nix_like,
+    classify_line_ocaml_like, classify_line_perl_like, classify_line_php_like,
+    classify_line_powershell_like, classify_line_python_like, classify_line_ruby_like,
+    classify_line_scheme_like, classify_line_shell_like, classify_line_sql_like,
+    classify_line_toml_like, classify_line_vb_like, classify_line_xml_like,
+    classify_line_yaml_like,
 };
+use cloc::model::{ParserKind, SqlDialect};
 
 #[test]
 fn c_like_inline_block_comment_code_both_sides() {
@@ -157,6 +170,7 @@ fn batch_rem_and_colon_colon_comments() {
     assert_eq!(classify_line_batch_like(":: hello"), (false, true));
     assert_eq!(classify_line_batch_like("echo REM hello"), (true, false));
     assert_eq!(classify_line_batch_like("set X=1"), (true, false));
+    assert_eq!(classify_line_batch_like(">?中\"\"\"-}"), (true, false));
 }
 
 #[test]
@@ -169,7 +183,7 @@ fn kts_uses_c_like_comments() {
 
 #[test]
 fn sql_trailing_line_comment() {
-    let mut st = ParseState::new();
+    let mut st = SqlState::default();
     let (code, comment) = classify_line_sql_like("select 1 -- hi", &mut st);
     assert!(code);
     assert!(comment);
@@ -177,27 +191,1413 @@ fn sql_trailing_line_comment() {
 
 #[test]
 fn sql_multiline_block_comment() {
-    let mut st = ParseState::new();
+    let mut st = SqlState::default();
     let (c1, m1) = classify_line_sql_like("/* start", &mut st);
     assert!(!c1);
     assert!(m1);
-    assert!(st.in_block_comment);
+    assert_eq!(st.comment_depth, 1);
 
     let (c2, m2) = classify_line_sql_like("middle", &mut st);
     assert!(!c2);
     assert!(m2);
-    assert!(st.in_block_comment);
+    assert_eq!(st.comment_depth, 1);
 
     let (c3, m3) = classify_line_sql_like("end */ select 1", &mut st);
     assert!(c3);
     assert!(m3);
-    assert!(!st.in_block_comment);
+    assert_eq!(st.comment_depth, 0);
 }
 
 #[test]
 fn sql_string_with_double_dash_is_not_comment() {
-    let mut st = ParseState::new();
+    let mut st = SqlState::default();
     let (code, comment) = classify_line_sql_like("select '--not comment'", &mut st);
     assert!(code);
     assert!(!comment);
 }
+
+#[test]
+fn sql_doubled_quote_and_backslash_by_dialect() {
+    // Standard SQL: `\` is not an escape, so the string ends after `a\`.
+    let mut st = SqlState::new(SqlDialect::Ansi);
+    assert_eq!(
+        classify_line_sql_like(r"select 'a\' -- c", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_sql_like("select 'it''s -- not' x", &mut st),
+        (true, false)
+    );
+
+    // MySQL: `\'` is escaped, so the string spans lines; `#` starts a comment.
+    let mut st = SqlState::new(SqlDialect::MySql);
+    assert_eq!(
+        classify_line_sql_like(r"select 'a\' -- c", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_sql_like("/* x */ -- y '", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_sql_like("# note", &mut st), (false, true));
+    assert_eq!(
+        classify_line_sql_like("select 1--2", &mut st),
+        (true, false)
+    );
+}
+
+#[test]
+fn sql_postgres_dollar_quotes_and_nested_comments() {
+    let mut st = SqlState::new(SqlDialect::Postgres);
+    assert_eq!(
+        classify_line_sql_like("create function f() returns int as $body$", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_sql_like("  -- inside body", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_sql_like("  select $1; $$ /* x */", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_sql_like("$body$ language sql;", &mut st),
+        (true, false)
+    );
+    assert!(st.dollar_tag.is_none());
+    assert!(st.body_tag.is_none());
+
+    assert_eq!(
+        classify_line_sql_like("/* a /* b */ still", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_sql_like("c */ select 1", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.comment_depth, 0);
+}
+
+#[test]
+fn sql_tsql_bracket_identifiers() {
+    let mut st = SqlState::new(SqlDialect::TSql);
+    assert_eq!(
+        classify_line_sql_like("select [a--b] from t", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_sql_like("# not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_sql_like("select [multi", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_sql_like("-- still ]]name] -- note", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.in_quote, None);
+}
+
+#[test]
+fn sql_postgres_routine_bodies_use_sql_rules() {
+    let mut st = SqlState::new(SqlDialect::Postgres);
+    assert_eq!(classify_line_sql_like("DO $$", &mut st), (true, false));
+    assert_eq!(
+        classify_line_sql_like("BEGIN -- start", &mut st),
+        (true, true)
+    );
+    assert_eq!(classify_line_sql_like("  /* block", &mut st), (false, true));
+    assert_eq!(classify_line_sql_like("  $$;", &mut st), (true, true));
+    assert_eq!(st, SqlState::new(SqlDialect::Postgres));
+
+    // Literals elsewhere stay opaque.
+    assert_eq!(
+        classify_line_sql_like("select $$a -- b$$;", &mut st),
+        (true, false)
+    );
+
+    // A non-SQL language named before the body.
+    assert_eq!(
+        classify_line_sql_like("create function f() language plpython3u as $py$", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_sql_like("# python -- x", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_sql_like("$py$;", &mut st), (true, false));
+    assert!(!st.opaque_body);
+    assert_eq!(
+        classify_line_sql_like("create function g() as $$ -- c $$", &mut st),
+        (true, true)
+    );
+}
+
+#[test]
+fn python_triple_quoted_assignment_is_code() {
+    let mut st = PythonState::new();
+    assert_eq!(
+        classify_line_python_like("x = \"\"\"literal\"\"\"", &mut st),
+        (true, false)
+    );
+
+    assert_eq!(
+        classify_line_python_like("sql = '''", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_python_like("  SELECT # not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_python_like("'''", &mut st), (true, false));
+    assert!(st.in_triple.is_none());
+}
+
+#[test]
+fn python_docstring_in_statement_position_is_comment() {
+    let mut st = PythonState::new();
+    assert_eq!(
+        classify_line_python_like("def f():", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_python_like("    r\"\"\"Raw doc.\"\"\"", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_python_like("    'single line doc'", &mut st),
+        (false, true)
+    );
+}
+
+#[test]
+fn python_triple_quoted_argument_in_open_call_is_code() {
+    let mut st = PythonState::new();
+    assert_eq!(classify_line_python_like("run(", &mut st), (true, false));
+    assert_eq!(
+        classify_line_python_like("    \"\"\"SELECT 1\"\"\",", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_python_like(")", &mut st), (true, false));
+    assert_eq!(st.bracket_depth, 0);
+}
+
+#[test]
+fn python_string_prefixes_and_escapes() {
+    let mut st = PythonState::new();
+    assert_eq!(
+        classify_line_python_like("p = r'C:\\#dir\\\\' # c", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_python_like("b = b\"\\\"#\"", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_python_like("s = f\"{'#'}\" + f\"{d[\"#\"]}\"", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_python_like("t = f'{{#}}' # c", &mut st),
+        (true, true)
+    );
+    assert!(st.in_triple.is_none());
+}
+
+#[test]
+fn python_closing_delimiter_is_not_found_inside_escape() {
+    let mut st = PythonState::new();
+    assert_eq!(
+        classify_line_python_like("\"\"\"doc", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_python_like("a \\\"\"\" still doc", &mut st),
+        (false, true)
+    );
+    assert!(st.in_triple.is_some());
+    assert_eq!(classify_line_python_like("\"\"\"", &mut st), (false, true));
+    assert!(st.in_triple.is_none());
+}
+
+#[test]
+fn shell_hash_only_comments_at_word_start() {
+    let mut st = ShellState::new();
+    assert_eq!(
+        classify_line_shell_like("echo ${path#/tmp} $# a#b", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_shell_like("ls # list", &mut st), (true, true));
+    assert_eq!(
+        classify_line_shell_like("  # only comment", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_shell_like("x=\"'''\" # c", &mut st),
+        (true, true)
+    );
+    assert!(st.in_quote.is_none());
+}
+
+#[test]
+fn shell_heredoc_body_is_not_classified_line_by_line() {
+    let mut st = ShellState::new();
+    assert_eq!(
+        classify_line_shell_like("cat <<-'EOF' > out # write", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_shell_like("# not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_shell_like("\tEOF", &mut st), (true, false));
+    assert!(st.heredocs.is_empty());
+    assert_eq!(
+        classify_line_shell_like("# comment again", &mut st),
+        (false, true)
+    );
+}
+
+#[test]
+fn shell_colon_heredoc_is_block_comment() {
+    let mut st = ShellState::new();
+    assert_eq!(
+        classify_line_shell_like(": <<'DOC'", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_shell_like("usage notes", &mut st),
+        (false, true)
+    );
+    assert_eq!(classify_line_shell_like("DOC", &mut st), (false, true));
+    assert!(st.heredocs.is_empty());
+}
+
+#[test]
+fn shell_quote_spanning_lines() {
+    let mut st = ShellState::new();
+    assert_eq!(
+        classify_line_shell_like("msg='first", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_shell_like("# inside quote", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_shell_like("end' # c", &mut st), (true, true));
+    assert!(st.in_quote.is_none());
+}
+
+#[test]
+fn yaml_block_scalar_content_is_code() {
+    let mut st = YamlState::new();
+    assert_eq!(
+        classify_line_yaml_like("script: |  # run it", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_yaml_like("  echo \"\"\"", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_yaml_like("  # kept verbatim", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_yaml_like("next: 1 # c", &mut st),
+        (true, true)
+    );
+    assert!(st.block_indent.is_none());
+    assert_eq!(classify_line_yaml_like("# comment", &mut st), (false, true));
+}
+
+#[test]
+fn yaml_hash_needs_whitespace_and_quotes_span_lines() {
+    let mut st = YamlState::new();
+    assert_eq!(
+        classify_line_yaml_like("url: http://x/#frag", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_yaml_like("--- # doc", &mut st), (true, true));
+    assert_eq!(
+        classify_line_yaml_like("msg: 'it''s", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_yaml_like("  # still quoted'", &mut st),
+        (true, false)
+    );
+    assert!(st.in_quote.is_none());
+}
+
+#[test]
+fn toml_multiline_strings_and_quoted_keys() {
+    let mut st = TomlState::new();
+    assert_eq!(
+        classify_line_toml_like("\"a#b\" = 1 # c", &mut st),
+        (true, true)
+    );
+    assert_eq!(classify_line_toml_like("[x.'y#z']", &mut st), (true, false));
+    assert_eq!(classify_line_toml_like("s = '''", &mut st), (true, false));
+    assert_eq!(classify_line_toml_like("# inside", &mut st), (true, false));
+    assert_eq!(
+        classify_line_toml_like("'''  # after", &mut st),
+        (true, true)
+    );
+    assert!(st.in_multiline.is_none());
+}
+
+#[test]
+fn lua_leveled_long_comment_needs_matching_close() {
+    let mut st = LuaState::new();
+    assert_eq!(
+        classify_line_lua_like("--[==[ start", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_lua_like("t[a[1]] = 1 ]] still comment", &mut st),
+        (false, true)
+    );
+    assert!(st.in_long_comment);
+    assert_eq!(classify_line_lua_like("]==] x = 1", &mut st), (true, true));
+    assert!(!st.in_long_comment);
+}
+
+#[test]
+fn lua_long_string_with_dashes_is_code() {
+    let mut st = LuaState::new();
+    assert_eq!(
+        classify_line_lua_like("local s = [[ -- not a comment", &mut st),
+        (true, false)
+    );
+    assert!(st.in_long_string);
+    assert_eq!(
+        classify_line_lua_like("--[[ still string", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_lua_like("]] -- trailing", &mut st),
+        (true, true)
+    );
+    assert!(!st.in_long_string);
+    assert_eq!(
+        classify_line_lua_like("x = [=[a]]b]=] --[[c]] y = 2", &mut st),
+        (true, true)
+    );
+    assert!(!st.in_long_comment);
+}
+
+#[test]
+fn html_script_and_style_regions_use_sub_parsers() {
+    let mut st = HtmlState::new();
+    assert_eq!(classify_line_html_like("<script>", &mut st), (true, false));
+    assert_eq!(st.region, HtmlRegion::Script);
+    assert_eq!(
+        classify_line_html_like("  // setup", &mut st),
+        (false, true)
+    );
+    assert_eq!(st.line_lang, Some("js"));
+    assert_eq!(
+        classify_line_html_like("  let a = 1; /* x */", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_html_like("</script><style>", &mut st),
+        (true, false)
+    );
+    assert_eq!(st.line_lang, None);
+    assert_eq!(
+        classify_line_html_like("a { color: red } /* c */", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.line_lang, Some("css"));
+    assert_eq!(classify_line_html_like("</style>", &mut st), (true, false));
+    assert_eq!(
+        classify_line_html_like("<p>// text</p>", &mut st),
+        (true, false)
+    );
+}
+
+#[test]
+fn html_script_template_literals_and_regexes_are_code() {
+    let mut st = HtmlState::new();
+    assert_eq!(classify_line_html_like("<script>", &mut st), (true, false));
+    assert_eq!(
+        classify_line_html_like("const url = `http://x/*`;", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_html_like("const tpl = `", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_html_like("  // inside the template", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_html_like(r"`; const re = /\/\*/;", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_html_like("next(); // done", &mut st),
+        (true, true)
+    );
+    assert_eq!(classify_line_html_like("</script>", &mut st), (true, false));
+    assert_eq!(st.script_state, JsState::new());
+}
+
+#[test]
+fn html_multiline_script_tag_with_lang_attribute() {
+    let mut st = HtmlState::new();
+    assert_eq!(
+        classify_line_html_like("<script setup", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_html_like("  lang=\"ts\">", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_html_like("const a: number = 1", &mut st),
+        (true, false)
+    );
+    assert_eq!(st.line_lang, Some("ts"));
+    assert_eq!(classify_line_html_like("</script>", &mut st), (true, false));
+    assert_eq!(
+        classify_line_html_like("<script src=\"x.js\" /> <!-- c -->", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.region, HtmlRegion::Markup);
+}
+
+#[test]
+fn markdown_prose_is_comment_and_fence_body_is_code() {
+    let mut st = MarkdownState::new();
+    assert_eq!(
+        classify_line_markdown_like("# Title", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_markdown_like("```rust,ignore", &mut st),
+        (false, true)
+    );
+    assert_eq!(st.fence_tag.as_deref(), Some("rust"));
+    assert_eq!(
+        classify_line_markdown_like("// not prose", &mut st),
+        (true, false)
+    );
+    assert!(st.in_fence_body);
+    // A shorter or different fence does not close the block.
+    assert_eq!(classify_line_markdown_like("~~~", &mut st), (true, false));
+    assert_eq!(classify_line_markdown_like("```", &mut st), (false, true));
+    assert!(!st.in_fence_body);
+    assert_eq!(st.fence, None);
+    assert_eq!(
+        classify_line_markdown_like("<!-- start", &mut st),
+        (false, true)
+    );
+    assert_eq!(classify_line_markdown_like("```", &mut st), (false, true));
+    assert_eq!(classify_line_markdown_like("-->", &mut st), (false, true));
+    assert_eq!(st.fence, None);
+}
+
+#[test]
+fn embedded_classifier_uses_language_rules() {
+    let mut py = Classifier::for_kind(ParserKind::Python).unwrap();
+    assert_eq!(py.classify("# note"), (false, true));
+    assert_eq!(py.classify("x = 1  # note"), (true, true));
+    let mut text = Classifier::for_kind(ParserKind::PlainText).unwrap();
+    assert_eq!(text.classify("// anything"), (true, false));
+    assert!(Classifier::for_kind(ParserKind::Notebook).is_none());
+}
+
+#[test]
+fn php_inline_html_tags_and_hash_comments() {
+    let mut st = PhpState::new();
+    assert_eq!(
+        classify_line_php_like("<!-- header -->", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_php_like("<?php # setup", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_php_like("#[Route('/')]", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_php_like("$a = 1; // done ?> <p>x</p>", &mut st),
+        (true, true)
+    );
+    assert!(!st.in_php);
+    assert_eq!(
+        classify_line_php_like("<p><?= $a ?></p>", &mut st),
+        (true, false)
+    );
+    assert!(!st.in_php);
+}
+
+#[test]
+fn php_heredoc_and_nowdoc_bodies_are_code() {
+    let mut st = PhpState::new();
+    st.in_php = true;
+    assert_eq!(
+        classify_line_php_like("$s = <<<EOT", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_php_like("  # not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_php_like("  EOT; // end", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.heredoc, None);
+    assert_eq!(
+        classify_line_php_like("$n = <<<'NOW'", &mut st),
+        (true, false)
+    );
+    assert_eq!(st.heredoc.as_deref(), Some("NOW"));
+    assert_eq!(
+        classify_line_php_like("NOWHERE /* text */", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_php_like("NOW;", &mut st), (true, false));
+    assert_eq!(st.heredoc, None);
+}
+
+#[test]
+fn ruby_begin_end_docs_heredocs_and_end_marker() {
+    let mut st = ScriptState::new();
+    assert_eq!(
+        classify_line_ruby_like("puts \"#{x}\" # say", &mut st),
+        (true, true)
+    );
+    assert_eq!(classify_line_ruby_like("=begin", &mut st), (false, true));
+    assert_eq!(classify_line_ruby_like("x = 1", &mut st), (false, true));
+    assert_eq!(classify_line_ruby_like("=end", &mut st), (false, true));
+    assert_eq!(
+        classify_line_ruby_like("sql = <<~SQL", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_ruby_like("  # not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_ruby_like("  SQL", &mut st), (true, false));
+    assert!(st.heredocs.is_empty());
+    assert_eq!(
+        classify_line_ruby_like("list <<item # append", &mut st),
+        (true, true)
+    );
+    assert!(st.heredocs.is_empty());
+    assert_eq!(classify_line_ruby_like("__END__", &mut st), (false, true));
+    assert_eq!(classify_line_ruby_like("data = 1", &mut st), (false, true));
+}
+
+#[test]
+fn perl_pod_data_section_and_dollar_hash() {
+    let mut st = ScriptState::new();
+    assert_eq!(
+        classify_line_perl_like("my $n = $#items; # last", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_perl_like("=head1 NAME", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_perl_like("my $x = 1;", &mut st),
+        (false, true)
+    );
+    assert_eq!(classify_line_perl_like("=cut", &mut st), (false, true));
+    assert!(!st.in_doc);
+    assert_eq!(
+        classify_line_perl_like("print <<\"EOT\";", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_perl_like("# body", &mut st), (true, false));
+    assert_eq!(classify_line_perl_like("EOT", &mut st), (true, false));
+    assert_eq!(classify_line_perl_like("__DATA__", &mut st), (false, true));
+    assert_eq!(classify_line_perl_like("a,b,c", &mut st), (false, true));
+}
+
+#[test]
+fn ruby_and_perl_match_operator_regex_is_code() {
+    let mut st = ScriptState::new();
+    assert_eq!(
+        classify_line_ruby_like("ok = x =~ /#/", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_ruby_like("x !~ /a\\/#/ # no", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_perl_like("print if $x =~ /#/;", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_perl_like("$x=~/#/ and f(); # yes", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_perl_like("my $n = $a / 2; # half", &mut st),
+        (true, true)
+    );
+}
+
+#[test]
+fn haskell_nested_block_comments_and_dash_operators() {
+    let mut st = NestedCommentState::new();
+    assert_eq!(
+        classify_line_haskell_like("{- outer {- inner -}", &mut st),
+        (false, true)
+    );
+    assert_eq!(st.depth, 1);
+    assert_eq!(
+        classify_line_haskell_like("still comment -} x = 1", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.depth, 0);
+    assert_eq!(
+        classify_line_haskell_like("a --> b", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_haskell_like("x = 1 --- note", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_haskell_like("{-# LANGUAGE GADTs #-}", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_haskell_like("s = \"{- not -}\"", &mut st),
+        (true, false)
+    );
+    assert_eq!(st.depth, 0);
+}
+
+#[test]
+fn ml_family_nested_comments() {
+    let mut st = NestedCommentState::new();
+    assert_eq!(
+        classify_line_ocaml_like("(* a (* b *) still *)", &mut st),
+        (false, true)
+    );
+    assert_eq!(st.depth, 0);
+    assert_eq!(
+        classify_line_ocaml_like("let x = a // b", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_fsharp_like("let x = a // b", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_fsharp_like("let f = (*)", &mut st),
+        (true, false)
+    );
+    assert_eq!(st.depth, 0);
+}
+
+#[test]
+fn c_nested_block_comments() {
+    let mut st = NestedCommentState::new();
+    assert_eq!(
+        classify_line_c_nested_like("/* a /* b */", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_c_nested_like("c */ val x = 1", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.depth, 0);
+    assert_eq!(
+        classify_line_c_nested_like("val s = \"/*\" // c", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.depth, 0);
+}
+
+#[test]
+fn clojure_discard_and_comment_forms() {
+    let mut st = LispState::new();
+    assert_eq!(
+        classify_line_clojure_like("(def x \\;) ; char", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_clojure_like("#_(println", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_clojure_like("   \"a ) b\")", &mut st),
+        (false, true)
+    );
+    assert_eq!(st.discard, None);
+    assert_eq!(
+        classify_line_clojure_like("(+ 1 #_2 3)", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.discard, None);
+    assert_eq!(
+        classify_line_clojure_like("(comment", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_clojure_like("  (start-server {:port 80}))", &mut st),
+        (false, true)
+    );
+    assert_eq!(st.discard, None);
+    assert_eq!(
+        classify_line_clojure_like("(defn f \"doc", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_clojure_like("  ; still doc\" [])", &mut st),
+        (true, false)
+    );
+    assert!(!st.in_string);
+}
+
+#[test]
+fn scheme_and_lisp_block_and_datum_comments() {
+    let mut st = LispState::new();
+    assert_eq!(
+        classify_line_scheme_like("#| a #| b |#", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_scheme_like("c |# (define x 1)", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.block_depth, 0);
+    assert_eq!(
+        classify_line_scheme_like("#;(display x)", &mut st),
+        (false, true)
+    );
+    assert_eq!(st.discard, None);
+    assert_eq!(
+        classify_line_lisp_like("(insert ?;) ; semi", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_lisp_like("(char= c #\\;)", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_lisp_like("#_foo", &mut st), (true, false));
+}
+
+#[test]
+fn fortran_fixed_form_uses_columns() {
+    assert_eq!(
+        classify_line_fortran_fixed_like("C     COMPUTE SUM"),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_fortran_fixed_like("*     note"),
+        (false, true)
+    );
+    // `C` in column 7 is code, not a comment mark.
+    assert_eq!(
+        classify_line_fortran_fixed_like("      CALL SUB(X)"),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_fortran_fixed_like("     !  X = X + 1"),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_fortran_fixed_like("   10 X = 'a!b' ! set"),
+        (true, true)
+    );
+    assert_eq!(classify_line_fortran_fixed_like("\tX = 1"), (true, false));
+}
+
+#[test]
+fn fortran_free_form_bang_comments() {
+    assert_eq!(classify_line_fortran_free_like("  ! note"), (false, true));
+    assert_eq!(
+        classify_line_fortran_free_like("c = 1 ! note"),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_fortran_free_like("s = 'it''s ! here'"),
+        (true, false)
+    );
+}
+
+#[test]
+fn cobol_indicator_column_and_areas() {
+    assert_eq!(
+        classify_line_cobol_like("000100* COMMENT LINE"),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_cobol_like("000200/ PAGE EJECT"),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_cobol_like("000300 PROCEDURE DIVISION."),
+        (true, false)
+    );
+    // `*` outside column 7 is code (multiplication), as is text in column 8.
+    assert_eq!(
+        classify_line_cobol_like("000400     COMPUTE A = B * C."),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_cobol_like("       MOVE 1 TO X. *> trailing"),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_cobol_like("       DISPLAY '*> not'."),
+        (true, false)
+    );
+    let with_ident = format!("{:<72}{}", "000500     STOP RUN.", "*>CHG001");
+    assert_eq!(classify_line_cobol_like(&with_ident), (true, false));
+}
+
+#[test]
+fn asm_dialect_comment_rules() {
+    let mut gas = AsmState::new(AsmDialect::Gas, false);
+    assert_eq!(
+        classify_line_asm_like("movl $1, %eax # one", &mut gas),
+        (true, true)
+    );
+    assert_eq!(classify_line_asm_like("/* start", &mut gas), (false, true));
+    assert_eq!(classify_line_asm_like("end */ ret", &mut gas), (true, true));
+    assert_eq!(
+        classify_line_asm_like(".ascii \"a#b\"", &mut gas),
+        (true, false)
+    );
+
+    let mut arm = AsmState::new(AsmDialect::Arm, false);
+    assert_eq!(
+        classify_line_asm_like("mov r0, #1 @ one", &mut arm),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_asm_like("# line comment", &mut arm),
+        (false, true)
+    );
+
+    let mut nasm = AsmState::new(AsmDialect::Nasm, false);
+    assert_eq!(
+        classify_line_asm_like("mov eax, ';' ; semi", &mut nasm),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_asm_like("push rbp # not a comment", &mut nasm),
+        (true, false)
+    );
+}
+
+#[test]
+fn asm_preprocessed_directives_are_code() {
+    let mut st = AsmState::new(AsmDialect::Gas, true);
+    assert_eq!(
+        classify_line_asm_like("#include <asm/unistd.h>", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_asm_like("# define N 4 // four", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_asm_like("# plain comment", &mut st),
+        (false, true)
+    );
+    let mut raw = AsmState::new(AsmDialect::Gas, false);
+    assert_eq!(
+        classify_line_asm_like("#include <x.h>", &mut raw),
+        (false, true)
+    );
+}
+
+#[test]
+fn asm_dialect_detection() {
+    assert_eq!(
+        AsmDialect::detect("section .text\nglobal _start\n; entry\n"),
+        AsmDialect::Nasm
+    );
+    assert_eq!(
+        AsmDialect::detect(".syntax unified\n@ setup\nmov r0, #0\n"),
+        AsmDialect::Arm
+    );
+    assert_eq!(
+        AsmDialect::detect("# entry\n.globl main\nmain:\n movl %esp, %ebp\n"),
+        AsmDialect::Gas
+    );
+}
+
+#[test]
+fn powershell_block_comments_and_here_strings() {
+    let mut st = PowerShellState::new();
+    assert_eq!(classify_line_powershell_like("<#", &mut st), (false, true));
+    assert_eq!(
+        classify_line_powershell_like(".SYNOPSIS #>", &mut st),
+        (false, true)
+    );
+    assert!(!st.in_block_comment);
+    assert_eq!(
+        classify_line_powershell_like("$x = 'a#b' # note", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_powershell_like("Write-Host a#b", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_powershell_like("$s = @\"", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_powershell_like("# not a comment <#", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_powershell_like("\"@ | Out-File x # save", &mut st),
+        (true, true)
+    );
+    assert_eq!(st.here_string, None);
+    assert!(!st.in_block_comment);
+}
+
+#[test]
+fn vb_quote_and_rem_comments() {
+    assert_eq!(classify_line_vb_like("' comment"), (false, true));
+    assert_eq!(
+        classify_line_vb_like("Dim s = \"it''s \"\"quoted\"\"\" ' note"),
+        (true, true)
+    );
+    assert_eq!(classify_line_vb_like("REM old style"), (false, true));
+    assert_eq!(classify_line_vb_like("x = 1 : Rem trailing"), (true, true));
+    assert_eq!(classify_line_vb_like("Remove(x)"), (true, false));
+    assert_eq!(classify_line_vb_like("x = rem"), (true, false));
+}
+
+#[test]
+fn hcl_comments_and_heredocs() {
+    let mut st = HclState::new();
+    assert_eq!(
+        classify_line_hcl_like("name = \"a#b\" // note", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_hcl_like("policy = <<-EOT", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_hcl_like("  # body", &mut st), (true, false));
+    assert_eq!(classify_line_hcl_like("  EOT", &mut st), (true, false));
+    assert_eq!(st.heredoc, None);
+    assert_eq!(classify_line_hcl_like("/* a", &mut st), (false, true));
+    assert_eq!(classify_line_hcl_like("b */ # c", &mut st), (false, true));
+}
+
+#[test]
+fn dockerfile_hash_only_at_line_start() {
+    assert_eq!(
+        classify_line_dockerfile_like("# syntax=docker/dockerfile:1"),
+        (false, true)
+    );
+    assert_eq!(classify_line_dockerfile_like("RUN echo #1"), (true, false));
+}
+
+#[test]
+fn makefile_recipe_lines_use_shell_rules() {
+    let mut st = MakefileState::new();
+    assert_eq!(
+        classify_line_makefile_like("CC := gcc # compiler", &mut st),
+        (true, true)
+    );
+    assert!(!st.in_recipe);
+    assert_eq!(
+        classify_line_makefile_like("all: main.o", &mut st),
+        (true, false)
+    );
+    assert!(st.in_recipe);
+    assert_eq!(
+        classify_line_makefile_like("\techo \"a#b\"", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_makefile_like("\t# shell comment", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_makefile_like("# make comment", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_makefile_like("ifdef DEBUG", &mut st),
+        (true, false)
+    );
+    assert!(st.in_recipe);
+    assert_eq!(
+        classify_line_makefile_like("X = a:b", &mut st),
+        (true, false)
+    );
+    assert!(!st.in_recipe);
+    assert_eq!(
+        classify_line_makefile_like("Y = \\#literal", &mut st),
+        (true, false)
+    );
+}
+
+#[test]
+fn nix_indented_strings() {
+    let mut st = NixState::new();
+    assert_eq!(
+        classify_line_nix_like("script = ''", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_nix_like("  # not a comment ''${x}", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_nix_like("''; # done", &mut st), (true, true));
+    assert_eq!(st.in_string, None);
+    assert_eq!(
+        classify_line_nix_like("/* block */ x = \"#\";", &mut st),
+        (true, true)
+    );
+}
+
+#[test]
+fn js_multiline_template_literal_with_nested_substitution() {
+    let mut st = JsState::new();
+    assert_eq!(
+        classify_line_js_like("const url = `http://x ${", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_js_like("  y ? `/* ${z} */` : {a: 1}[k]", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_js_like("} // still template", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_js_like("`; // done", &mut st), (true, true));
+    assert!(!st.in_template);
+    assert!(st.template_braces.is_empty());
+}
+
+#[test]
+fn js_regex_literal_vs_division() {
+    let mut st = JsState::new();
+    assert_eq!(
+        classify_line_js_like(r"const re = /\/\//g;", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_js_like("if (x) return /[/*]/.test(s);", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_js_like("let r = a / b; // half", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_js_like("let q = (a) / 2 /* c */;", &mut st),
+        (true, true)
+    );
+    assert!(!st.in_block_comment);
+}
+
+#[test]
+fn cpp_multiline_raw_string_contains_comment_markers() {
+    let mut st = ParseState::new();
+    assert_eq!(
+        classify_line_cpp_like(r#"auto q = R"sql(select 1"#, &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_cpp_like("  -- )\" // /* not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_cpp_like(r#")sql"; // done"#, &mut st),
+        (true, true)
+    );
+    assert!(st.raw_string_end.is_none());
+    assert!(!st.in_block_comment);
+}
+
+#[test]
+fn cpp_digit_separators_are_not_char_literals() {
+    let mut st = ParseState::new();
+    assert_eq!(
+        classify_line_cpp_like("int n = 1'000'000; // million", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_cpp_like("auto m = 0xFF'FF; char c = '/';", &mut st),
+        (true, false)
+    );
+}
+
+#[test]
+fn cpp_line_comment_continued_by_backslash() {
+    let mut st = ParseState::new();
+    assert_eq!(
+        classify_line_cpp_like("int x; // note \\", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_cpp_like("   still the comment", &mut st),
+        (false, true)
+    );
+    assert_eq!(classify_line_cpp_like("int y;", &mut st), (true, false));
+}
+
+#[test]
+fn go_multiline_raw_string_is_code() {
+    let mut st = ParseState::new();
+    assert_eq!(classify_line_go_like("q := `", &mut st), (true, false));
+    assert_eq!(
+        classify_line_go_like("  -- comment in SQL /* x", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_go_like("` // end", &mut st), (true, true));
+    assert!(st.raw_string_end.is_none());
+    assert!(!st.in_block_comment);
+}
+
+#[test]
+fn csharp_verbatim_and_raw_strings_are_code() {
+    let mut st = ParseState::new();
+    assert_eq!(
+        classify_line_csharp_like(r#"var q = @"select ""a"" "#, &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_csharp_like(r#"  // not a comment"#, &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_csharp_like(r#"  from t"; // done"#, &mut st),
+        (true, true)
+    );
+
+    assert_eq!(
+        classify_line_csharp_like(r##"var r = $""""##, &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_csharp_like(r#"  /* "quoted" */"#, &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_csharp_like(r#"  """;"#, &mut st),
+        (true, false)
+    );
+    assert!(st.raw_string_end.is_none());
+    assert_eq!(
+        classify_line_csharp_like(r#"var e = ""; // empty"#, &mut st),
+        (true, true)
+    );
+}
+
+#[test]
+fn shell_arithmetic_shift_is_not_heredoc() {
+    let mut st = ShellState::new();
+    assert_eq!(
+        classify_line_shell_like("x=$((1 << 2)) # shift", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_shell_like("((x <<= 1))", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_shell_like("cat << 2", &mut st), (true, false));
+    assert!(st.heredocs.is_empty());
+    assert_eq!(
+        classify_line_shell_like("# still a comment", &mut st),
+        (false, true)
+    );
+}
+
+#[test]
+fn html_blank_lines_inside_regions_name_the_embedded_language() {
+    let mut st = HtmlState::new();
+    classify_line_html_like("<style lang=\"scss\">", &mut st);
+    assert_eq!(classify_line_html_like("", &mut st), (false, false));
+    assert_eq!(st.line_lang, Some("scss"));
+    classify_line_html_like("</style>", &mut st);
+    assert_eq!(classify_line_html_like("   ", &mut st), (false, false));
+    assert_eq!(st.line_lang, None);
+}
+
+#[test]
+fn html_lang_attribute_matches_whole_names_only() {
+    let mut st = HtmlState::new();
+    classify_line_html_like("<script data-lang=\"ts\" xml:lang=\"ts\">", &mut st);
+    assert_eq!(classify_line_html_like("let a = 1", &mut st), (true, false));
+    assert_eq!(st.line_lang, Some("js"));
+    classify_line_html_like("</script>", &mut st);
+    classify_line_html_like("<script\tlang=\"ts\">", &mut st);
+    classify_line_html_like("let a = 1", &mut st);
+    assert_eq!(st.line_lang, Some("ts"));
+}
+
+#[test]
+fn html_non_script_types_are_plain_text() {
+    let mut st = HtmlState::new();
+    classify_line_html_like("<script type=\"application/ld+json\">", &mut st);
+    assert_eq!(
+        classify_line_html_like("{\"url\": \"http://x\"}", &mut st),
+        (true, false)
+    );
+    assert_eq!(st.line_lang, Some("txt"));
+    classify_line_html_like("</script>", &mut st);
+    classify_line_html_like("<script type=\"text/template\">", &mut st);
+    assert_eq!(
+        classify_line_html_like("// {{ name }}", &mut st),
+        (true, false)
+    );
+    classify_line_html_like("</script>", &mut st);
+    classify_line_html_like("<script type=\"module\">", &mut st);
+    assert_eq!(classify_line_html_like("// c", &mut st), (false, true));
+    assert_eq!(st.line_lang, Some("js"));
+}
+
+#[test]
+fn markdown_blank_lines_inside_fences_are_fence_body() {
+    let mut st = MarkdownState::new();
+    classify_line_markdown_like("```rust", &mut st);
+    assert_eq!(classify_line_markdown_like("", &mut st), (false, false));
+    assert!(st.in_fence_body);
+    classify_line_markdown_like("```", &mut st);
+    classify_line_markdown_like("", &mut st);
+    assert!(!st.in_fence_body);
+}
+
+#[test]
+fn perl_hash_inside_quote_like_operators_is_code() {
+    let mut st = ScriptState::new();
+    assert_eq!(
+        classify_line_perl_like("$line =~ s/#.*//;", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_perl_like("$n = ($s =~ tr/#//);", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_perl_like("$s =~ y/#/x/; # count", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_perl_like("s{#(\\w+)} {<$1>}g;", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_perl_like("my $re = qr#a/b#;", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_perl_like("if (m[#\\]]) { }", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_perl_like("my %h = (s => 1); # s is a key", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_perl_like("print $s; # sum", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_perl_like("s #not a delimiter", &mut st),
+        (true, true)
+    );
+}
+
+#[test]
+fn c_nested_triple_quoted_strings_span_lines() {
+    let mut st = NestedCommentState::new();
+    assert_eq!(
+        classify_line_c_nested_like("val s = \"\"\"", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_c_nested_like("  // not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_c_nested_like("  /* nor this", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_c_nested_like("\"\"\" // done", &mut st),
+        (true, true)
+    );
+    assert_eq!(st, NestedCommentState::new());
+    assert_eq!(
+        classify_line_c_nested_like("let q = \"\"\"\"a\"\"\"\" // x", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_c_nested_like("var d = '''", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_c_nested_like("/* text */'''; x", &mut st),
+        (true, false)
+    );
+    assert_eq!(st.triple_quote, None);
+    assert_eq!(
+        classify_line_c_nested_like("val c = '\"' // q", &mut st),
+        (true, true)
+    );
+}
+
+#[test]
+fn makefile_define_bodies_are_literal() {
+    let mut st = MakefileState::new();
+    assert_eq!(
+        classify_line_makefile_like("define HELP # usage", &mut st),
+        (true, true)
+    );
+    assert_eq!(
+        classify_line_makefile_like("# not a comment", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_makefile_like("  define INNER", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_makefile_like("  endef", &mut st),
+        (true, false)
+    );
+    assert_eq!(
+        classify_line_makefile_like("\techo #x", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_makefile_like("endef", &mut st), (true, false));
+    assert_eq!(st.define_depth, 0);
+    assert_eq!(
+        classify_line_makefile_like("# comment", &mut st),
+        (false, true)
+    );
+    assert_eq!(
+        classify_line_makefile_like("override define X =", &mut st),
+        (true, false)
+    );
+    assert_eq!(classify_line_makefile_like("#x", &mut st), (true, false));
+    assert_eq!(classify_line_makefile_like("endef", &mut st), (true, false));
+    assert_eq!(
+        classify_line_makefile_like("undefine X # drop", &mut st),
+        (true, true)
+    );
+}
@@ -0,0 +1,109 @@
+use cloc::detect::{find_duplicates, is_generated_marker, is_lock_file, is_minified};
+use cloc::model::CodeFileData;
+use sha2::{Digest, Sha256};
+
+fn file(path: &str, content: &[u8]) -> CodeFileData {
+    let mut f = CodeFileData::new(path.to_string(), "rs".to_string());
+    f.set_content(Some(Sha256::digest(content).into()), content.len() as u64);
+    f
+}
+
+#[test]
+fn generated_banner_in_comment() {
+    assert!(is_generated_marker(
+        "// Code generated by protoc-gen-go. DO NOT EDIT.",
+        true,
+        false
+    ));
+    assert!(is_generated_marker("# @generated by tool", true, false));
+    assert!(is_generated_marker(
+        "// Generated by the protocol buffer compiler.",
+        true,
+        false
+    ));
+}
+
+#[test]
+fn generated_banner_outside_comment_is_ignored() {
+    assert!(!is_generated_marker("s = \"@generated\"", false, false));
+    assert!(!is_generated_marker("// hand written", true, false));
+}
+
+#[test]
+fn lockfile_json_is_generated() {
+    assert!(is_generated_marker(
+        "  \"lockfileVersion\": 3,",
+        false,
+        true
+    ));
+    assert!(is_generated_marker("lockfileVersion: '9.0'", false, true));
+}
+
+#[test]
+fn lockfile_marker_only_counts_in_lock_files() {
+    assert!(is_lock_file("web/package-lock.json"));
+    assert!(is_lock_file("npm-shrinkwrap.json"));
+    assert!(is_lock_file("pnpm-lock.yaml"));
+    assert!(!is_lock_file("src/config.json"));
+    assert!(!is_lock_file("Cargo.lock"));
+    assert!(!is_lock_file("docs/lockfile.md"));
+    assert!(!is_generated_marker(
+        "const key = \"lockfileVersion\";",
+        false,
+        false
+    ));
+}
+
+#[test]
+fn minified_by_name_source_map_or_line_length() {
+    assert!(is_minified("dist/app.min.js", "var a = 1;\n", true));
+    assert!(is_minified(
+        "a.css",
+        "a{}\n/*# sourceMappingURL=a.css.map */\n",
+        true
+    ));
+    assert!(is_minified("bundle.js", &"var a=1;".repeat(100), true));
+    assert!(!is_minified("src/app.js", "var a = 1;\nvar b = 2;\n", true));
+}
+
+#[test]
+fn duplicates_grouped_by_content_with_kept_file_first() {
+    let files = [
+        file("vendor/b.rs", b"fn x() {}\n"),
+        file("src/a.rs", b"fn x() {}\n"),
+        // Same length, different bytes.
+        file("src/c.rs", b"fn y() {}\n"),
+    ];
+    assert_eq!(find_duplicates(&files), vec![vec![1, 0]]);
+}
+
+#[test]
+fn duplicates_keep_one_copy_of_a_repeated_path() {
+    let files = [file("src/a.rs", b"x"), file("src/a.rs", b"x")];
+    assert_eq!(find_duplicates(&files), vec![vec![0, 1]]);
+}
+
+#[test]
+fn empty_files_are_not_duplicates() {
+    let files = [file("a/__init__.py", b""), file("b/__init__.py", b"")];
+    assert!(find_duplicates(&files).is_empty());
+}
+
+#[test]
+fn long_lines_only_mark_assets_as_minified() {
+    // A notebook with an embedded base64 image output on one line.
+    let notebook = format!(
+        "{{\n \"cells\": [],\n \"outputs\": \"{}\"\n}}\n",
+        "iVBORw0KGgo".repeat(4000)
+    );
+    assert!(!is_minified("analysis.ipynb", &notebook, false));
+    assert!(!is_minified("notes.txt", &"x".repeat(1000), false));
+}
+
+#[test]
+fn line_length_counts_characters_not_bytes() {
+    // 150 CJK characters per line: 450 bytes, but well under the character limit.
+    let prose = format!("{}\n", "统计代码行数".repeat(25)).repeat(10);
+    assert!(!is_minified("docs/guide.md", &prose, false));
+    assert!(!is_minified("i18n/zh.js", &prose, true));
+}
@@ -0,0 +1,35 @@
+use cloc::notebook::{CellKind, parse_notebook};
+
+#[test]
+fn notebook_cells_keep_sources_and_drop_outputs() {
+    let nb = parse_notebook(
+        r##"{
+ "cells": [
+  {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "text"]},
+  {"cell_type": "code", "execution_count": 1, "metadata": {},
+   "outputs": [{"output_type": "stream", "text": ["out\n"]}],
+   "source": "import os\nx = 1"}
+ ],
+ "metadata": {"kernelspec": {"language": "Python", "name": "python3"}},
+ "nbformat": 4
+}"##,
+    )
+    .unwrap();
+    assert_eq!(nb.language, "python");
+    assert_eq!(nb.cells.len(), 2);
+    assert_eq!(nb.cells[0].kind, CellKind::Markdown);
+    assert_eq!(nb.cells[0].source, "# Title\ntext");
+    assert_eq!(nb.cells[1].kind, CellKind::Code);
+    assert_eq!(nb.cells[1].source, "import os\nx = 1");
+}
+
+#[test]
+fn notebook_language_falls_back_to_language_info_then_python() {
+    let nb =
+        parse_notebook(r#"{"cells": [], "metadata": {"language_info": {"name": "R"}}}"#).unwrap();
+    assert_eq!(nb.language, "r");
+    let nb = parse_notebook(r#"{"cells": []}"#).unwrap();
+    assert_eq!(nb.language, "python");
+    assert!(parse_notebook("{\"a\": 1}").is_none());
+    assert!(parse_notebook("not json").is_none());
+}